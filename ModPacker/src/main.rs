@@ -5,7 +5,6 @@ mod utils;
 use crate::serialize::ModJson;
 use std::fs::File;
 use std::path::{Path, PathBuf};
-use std::str::FromStr;
 use std::{env, fs, io};
 use std::process::{Command, Stdio};
 use bytebuffer::ByteBuffer;
@@ -74,8 +73,7 @@ fn wrapper() -> Result<(), String> {
             // the generated resource file will be at "TMP/<modid>/compiled.r"
         }
 
-        let appdata = env::var("APPDATA").mets()?;
-        let appdata = PathBuf::from_str(&appdata).mets()?;
+        let appdata = utils::data_dir()?;
         let appdata = appdata.join("FiModPacker");
         let appdata = appdata.join("TMP");
         let temp_dir = appdata.join(&mod_json.modid);
@@ -129,8 +127,7 @@ fn compile_resources(modid: &str, dir: &PathBuf) -> Result<(), String> {
     println!("Preparing resources compilation...");
     let r_file_path = dir.join("src/res.rs");
     let resources_dir_file_path = dir.join("resources");
-    let appdata = env::var("APPDATA").mets()?;
-    let appdata = PathBuf::from_str(&appdata).mets()?;
+    let appdata = utils::data_dir()?;
     let appdata = appdata.join("FiModPacker");
     let appdata = appdata.join("TMP");
     let appdata = appdata.join(modid);
@@ -1,11 +1,53 @@
 use std::fs;
 use std::io::Write;
-use std::path::Path;
+use std::env;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::str::FromStr;
 use toml_edit::{Document, DocumentMut, Item};
 use crate::MapErrorToString;
 
+/// Same as `ROOT_ENV` and `ROOT_FLAG` in the paths module of the server.
+pub const ROOT_ENV: &str = "FACTORYISLAND_HOME";
+pub const ROOT_FLAG: &str = "--data-dir";
+
+fn root_from_args() -> Option<PathBuf> {
+    let mut args = env::args();
+    while let Some(arg) = args.next() {
+        if arg == ROOT_FLAG {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix(ROOT_FLAG).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+fn non_empty_var(name: &str) -> Option<PathBuf> {
+    env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from)
+}
+
+fn platform_root() -> Option<PathBuf> {
+    if cfg!(windows) {
+        non_empty_var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        non_empty_var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        non_empty_var("XDG_DATA_HOME").or_else(|| non_empty_var("HOME").map(|home| home.join(".local/share")))
+    }
+}
+
+/// Where the packer keeps its temporary files. The packer does not depend on the server crate, so this mirrors
+/// `paths::storage_root` of the server and has to be kept in sync with it: the [`ROOT_FLAG`] command line flag, the
+/// [`ROOT_ENV`] environment variable, the platform data directory and as a last resort the working directory.
+pub fn data_dir() -> Result<PathBuf, String> {
+    match root_from_args().or_else(|| non_empty_var(ROOT_ENV)).or_else(platform_root) {
+        Some(dir) => Ok(dir),
+        None => env::current_dir().mets(),
+    }
+}
+
 //this is all chatgpt lmao i cannot be asked
 pub fn fix_relative_dependency_paths(
     src_cargo: impl AsRef<Path>,
@@ -0,0 +1,57 @@
+use crate::command::{world_arg, CommandExecutor, CommandSender};
+use crate::world::backup;
+use crate::{FactoryIsland, PLAYERS};
+
+pub struct BackupCommand;
+
+const USAGE: &str = "Usage: backup [create [world]|list [world]|restore <backup> <new world name>]";
+
+impl CommandExecutor for BackupCommand {
+    fn on_command(&mut self, sender: CommandSender, _: String, args: Vec<String>, fi: &mut FactoryIsland) {
+        if let CommandSender::Player(_) = &sender {
+            sender.send_error_message("This command can only be ran through console".to_string());
+            return;
+        }
+
+        match args.first().map(String::as_str) {
+            None | Some("create") => {
+                for player in PLAYERS.read().values() {
+                    player.lock().save();
+                }
+                let Some(world) = world_arg(&sender, fi, args.get(1)) else {
+                    return;
+                };
+                let mut world = world.lock();
+                sender.send_message("Saving world...".to_string());
+                match backup::start_backup(&mut world, fi.settings.backup_retention) {
+                    Ok(true) => sender.send_message("Backup started, it is written in the background".to_string()),
+                    Ok(false) => sender.send_error_message("Another backup of this world is still being written".to_string()),
+                    Err(e) => sender.send_error_message(format!("Backup failed: {e}")),
+                }
+            }
+            Some("list") => {
+                let world = args.get(1).cloned().unwrap_or_else(|| fi.worlds.default_name().to_string());
+                let backups = backup::list_backups(Some(&world));
+                if backups.is_empty() {
+                    sender.send_message(format!("There are no backups of {world}"));
+                }
+                for info in backups {
+                    let name = info.path.file_name().map_or(String::new(), |n| n.to_string_lossy().to_string());
+                    sender.send_message(format!("{name} ({} KiB)", info.size / 1024));
+                }
+            }
+            Some("restore") if args.len() == 3 => {
+                let Some(path) = backup::find_backup(&args[1]) else {
+                    sender.send_error_message(format!("Backup {} not found", args[1]));
+                    return;
+                };
+                sender.send_message(format!("Restoring {path:?} as {}...", args[2]));
+                match backup::restore(&path, &args[2]) {
+                    Ok(_) => sender.send_message(format!("Restored as world {}!", args[2])),
+                    Err(e) => sender.send_error_message(format!("Restore failed: {e}")),
+                }
+            }
+            _ => sender.send_error_message(USAGE.to_string()),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::command::{CommandExecutor, CommandSender};
+use crate::command::{world_arg, CommandExecutor, CommandSender};
 use crate::FactoryIsland;
 
 pub struct ChunksCommand;
@@ -10,19 +10,25 @@ impl CommandExecutor for ChunksCommand {
             return;
         }
         
-        if args.len() != 4 {
-            sender.send_error_message("Usage: chunks <x1> <z1> <x2> <z2>".to_string());
+        if args.len() != 4 && args.len() != 5 {
+            sender.send_error_message("Usage: chunks <x1> <z1> <x2> <z2> [world]".to_string());
             return;
         }
 
+        let Some(world) = world_arg(&sender, fi, args.get(4)) else {
+            return;
+        };
+
         let parse_arg = |s: &str| s.parse::<i32>();
 
         match (parse_arg(&args[0]), parse_arg(&args[1]), parse_arg(&args[2]), parse_arg(&args[3])) {
             (Ok(x1), Ok(z1), Ok(x2), Ok(z2)) => {
-                let world = fi.world.lock();
+                let world = world.lock();
                 for z in (z1..=z2).rev() {
                     for x in x1..=x2 {
-                        if world.is_loaded((x, z)) {
+                        if world.is_forced((x, z)) {
+                            sender.send_message_raw("F ".to_string());
+                        } else if world.is_loaded((x, z)) {
                             sender.send_message_raw("l ".to_string());
                         } else if world.exists_file((x, z)) {
                             sender.send_message_raw("* ".to_string());
@@ -0,0 +1,74 @@
+use crate::command::{world_arg, CommandExecutor, CommandSender};
+use crate::FactoryIsland;
+
+pub struct ForceloadCommand;
+
+const USAGE: &str = "Usage: forceload <add <x> <z>|remove <x> <z>|list|limit <max>> [world]";
+
+impl CommandExecutor for ForceloadCommand {
+    fn on_command(&mut self, sender: CommandSender, _: String, args: Vec<String>, fi: &mut FactoryIsland) {
+        if let CommandSender::Player(_) = &sender {
+            sender.send_error_message("This command can only be ran through console".to_string());
+            return;
+        }
+
+        match (args.first().map(String::as_str), args.len()) {
+            (Some(action @ ("add" | "remove")), 3 | 4) => {
+                let (Ok(x), Ok(z)) = (args[1].parse::<i32>(), args[2].parse::<i32>()) else {
+                    sender.send_error_message("Invalid arguments. All coordinates must be integers.".to_string());
+                    return;
+                };
+                let Some(world) = world_arg(&sender, fi, args.get(3)) else {
+                    return;
+                };
+                let mut world = world.lock();
+                if action == "add" {
+                    match world.force_chunk((x, z)) {
+                        Ok(true) => sender.send_message(format!("Chunk {x} {z} is now forced!")),
+                        Ok(false) => sender.send_error_message(format!("Chunk {x} {z} is already forced")),
+                        Err(e) => sender.send_error_message(e),
+                    }
+                } else {
+                    let loaders = world.chunk_loaders_in((x, z));
+                    match (world.unforce_chunk((x, z)), loaders) {
+                        (true, 0) => sender.send_message(format!("Chunk {x} {z} is no longer forced!")),
+                        (true, _) => sender.send_message(format!("Chunk {x} {z} is no longer forced, {loaders} chunk loaders still keep it loaded!")),
+                        (false, 0) => sender.send_error_message(format!("Chunk {x} {z} is not forced")),
+                        (false, _) => sender.send_error_message(format!("Chunk {x} {z} is only forced by {loaders} chunk loaders, break them to unforce it")),
+                    }
+                }
+            }
+            (Some("list"), 1 | 2) => {
+                let Some(world) = world_arg(&sender, fi, args.get(1)) else {
+                    return;
+                };
+                let world = world.lock();
+                let meta = world.meta();
+                sender.send_message(format!("Forced chunks of {} ({}/{}):", world.name(), meta.forced_count(), meta.max_forced_chunks));
+                for (x, z) in &meta.forced_chunks {
+                    sender.send_message(format!("{x} {z}"));
+                }
+                for ((x, z), loaders) in meta.loader_chunks.iter().filter(|(pos, _)| !meta.forced_chunks.contains(*pos)) {
+                    sender.send_message(format!("{x} {z} ({loaders} chunk loaders)"));
+                }
+            }
+            (Some("limit"), 2 | 3) => {
+                let Ok(max) = args[1].parse::<u16>() else {
+                    sender.send_error_message("The limit must be a positive integer".to_string());
+                    return;
+                };
+                let Some(world) = world_arg(&sender, fi, args.get(2)) else {
+                    return;
+                };
+                let mut world = world.lock();
+                world.set_max_forced_chunks(max);
+                sender.send_message(format!("{} can now force {max} chunks!", world.name()));
+                let forced = world.meta().forced_count();
+                if forced > max as usize {
+                    sender.send_message(format!("{forced} chunks are forced already and stay forced until removed"));
+                }
+            }
+            _ => sender.send_error_message(USAGE.to_string()),
+        }
+    }
+}
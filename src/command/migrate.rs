@@ -0,0 +1,26 @@
+use crate::command::{CommandExecutor, CommandSender};
+use crate::FactoryIsland;
+
+pub struct MigrateCommand;
+
+impl CommandExecutor for MigrateCommand {
+    fn on_command(&mut self, sender: CommandSender, _: String, _: Vec<String>, fi: &mut FactoryIsland) {
+        if let CommandSender::Player(_) = &sender {
+            sender.send_error_message("This command can only be ran through console".to_string());
+            return;
+        }
+
+        for world in fi.worlds.loaded() {
+            let mut world = world.lock();
+            if world.is_backing_up() {
+                sender.send_error_message(format!("A backup of {} is being written, try again once it is done", world.name()));
+                continue;
+            }
+            sender.send_message(format!("Saving {}...", world.name()));
+            world.save();
+            sender.send_message("Converting chunk files to regions...".to_string());
+            let migrated = world.migrate_chunks();
+            sender.send_message(format!("Converted {migrated} chunks!"));
+        }
+    }
+}
@@ -3,19 +3,34 @@ pub mod chunks;
 pub mod stop;
 pub mod save;
 pub mod commands;
+pub mod migrate;
+pub mod upgrade;
+pub mod backup;
+pub mod world;
+pub mod forceload;
+pub mod time;
+pub mod tps;
 
 use std::collections::HashMap;
 use log::{debug, info, warn};
 use mvutils::{enum_val, lazy};
 use mvutils::unsafe_utils::DangerousCell;
 use parking_lot::RwLock;
+use crate::command::backup::BackupCommand;
 use crate::command::chunks::ChunksCommand;
 use crate::command::commands::CommandsCommand;
+use crate::command::forceload::ForceloadCommand;
+use crate::command::migrate::MigrateCommand;
+use crate::command::upgrade::UpgradeCommand;
+use crate::command::world::WorldCommand;
 use crate::command::players::PlayersCommand;
 use crate::command::save::SaveCommand;
 use crate::command::stop::StopCommand;
+use crate::command::time::TimeCommand;
+use crate::command::tps::TpsCommand;
 use crate::FactoryIsland;
 use crate::server::packets::common::PlayerData;
+use crate::world::WorldType;
 
 lazy! {
     pub static COMMAND_PROCESSOR: CommandProcessor = CommandProcessor::new();
@@ -160,10 +175,32 @@ pub trait CommandExecutor {
     fn on_command(&mut self, sender: CommandSender, cmd: String, args: Vec<String>, fi: &mut FactoryIsland);
 }
 
+/// The loaded world named by the optional world argument of a command, the default world without one. Tells the
+/// sender if the named world is not loaded.
+pub(crate) fn world_arg(sender: &CommandSender, fi: &FactoryIsland, name: Option<&String>) -> Option<WorldType> {
+    match name {
+        Some(name) => {
+            let world = fi.worlds.get(name);
+            if world.is_none() {
+                sender.send_error_message(format!("World {name} is not loaded"));
+            }
+            world
+        }
+        None => Some(fi.worlds.default_world()),
+    }
+}
+
 pub(crate) fn register_commands() {
     COMMAND_PROCESSOR.register(Command::new("players", vec![], None, PlayersCommand).unwrap());
     COMMAND_PROCESSOR.register(Command::new("chunks", vec![], None, ChunksCommand).unwrap());
     COMMAND_PROCESSOR.register(Command::new("save", vec![], None, SaveCommand).unwrap());
     COMMAND_PROCESSOR.register(Command::new("stop", vec![], None, StopCommand).unwrap());
     COMMAND_PROCESSOR.register(Command::new("commands", vec![], None, CommandsCommand).unwrap());
+    COMMAND_PROCESSOR.register(Command::new("migrate", vec![], None, MigrateCommand).unwrap());
+    COMMAND_PROCESSOR.register(Command::new("upgrade", vec![], None, UpgradeCommand).unwrap());
+    COMMAND_PROCESSOR.register(Command::new("backup", vec![], Some("backup [create [world]|list [world]|restore <backup> <new world name>]"), BackupCommand).unwrap());
+    COMMAND_PROCESSOR.register(Command::new("world", vec![], Some("world <list|info <name>|create <name> [seed] [preset]|delete <name> [confirm]|load <name>|unload <name>|tp <player> <world>>"), WorldCommand).unwrap());
+    COMMAND_PROCESSOR.register(Command::new("forceload", vec![], Some("forceload <add <x> <z>|remove <x> <z>|list|limit <max>> [world]"), ForceloadCommand).unwrap());
+    COMMAND_PROCESSOR.register(Command::new("time", vec![], Some("time [set <ticks|sunrise|noon|sunset|midnight>|daylength <ticks>] [world]"), TimeCommand).unwrap());
+    COMMAND_PROCESSOR.register(Command::new("tps", vec!["mspt"], None, TpsCommand).unwrap());
 }
\ No newline at end of file
@@ -5,9 +5,8 @@ pub struct SaveCommand;
 
 impl CommandExecutor for SaveCommand {
     fn on_command(&mut self, sender: CommandSender, cmd: String, args: Vec<String>, fi: &mut FactoryIsland) {
-        let mut world = fi.world.lock();
-        sender.send_message("Saving world...".to_string());
-        world.save();
-        sender.send_message("World saved!".to_string());
+        sender.send_message("Saving worlds...".to_string());
+        fi.worlds.save_all();
+        sender.send_message("Worlds saved!".to_string());
     }
 }
\ No newline at end of file
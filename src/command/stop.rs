@@ -5,12 +5,8 @@ pub struct StopCommand;
 
 impl CommandExecutor for StopCommand {
     fn on_command(&mut self, sender: CommandSender, cmd: String, args: Vec<String>, fi: &mut FactoryIsland) {
-        let mut world = fi.world.lock();
-        sender.send_message("Saving world...".to_string());
-        world.save();
-        drop(world);
-        sender.send_message("World saved!".to_string());
-        sender.send_message("Exiting...".to_string());
-        fi.stop();
+        let reason = if args.is_empty() { "Server closed".to_string() } else { args.join(" ") };
+        sender.send_message("Stopping server...".to_string());
+        fi.stop(&reason);
     }
 }
\ No newline at end of file
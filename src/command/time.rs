@@ -0,0 +1,63 @@
+use crate::command::{world_arg, CommandExecutor, CommandSender};
+use crate::server::TPS;
+use crate::FactoryIsland;
+
+pub struct TimeCommand;
+
+const USAGE: &str = "Usage: time [set <ticks|sunrise|noon|sunset|midnight>|daylength <ticks>] [world]";
+
+impl CommandExecutor for TimeCommand {
+    fn on_command(&mut self, sender: CommandSender, _: String, args: Vec<String>, fi: &mut FactoryIsland) {
+        if let CommandSender::Player(_) = &sender {
+            sender.send_error_message("This command can only be ran through console".to_string());
+            return;
+        }
+
+        let name = match args.first().map(String::as_str) {
+            Some("set" | "daylength") => args.get(2),
+            _ => args.first(),
+        };
+        let Some(world) = world_arg(&sender, fi, name) else {
+            return;
+        };
+        let mut world = world.lock();
+
+        match (args.first().map(String::as_str), args.len()) {
+            (Some("set"), 2 | 3) => {
+                let day_length = world.day_length();
+                let time = match args[1].as_str() {
+                    "sunrise" => Some(0),
+                    "noon" => Some(day_length / 4),
+                    "sunset" => Some(day_length / 2),
+                    "midnight" => Some(day_length / 4 * 3),
+                    ticks => ticks.parse::<u32>().ok(),
+                };
+                let Some(time) = time else {
+                    sender.send_error_message(USAGE.to_string());
+                    return;
+                };
+                world.set_time_of_day(time);
+                sender.send_message(format!("Set the time of {} to {}", world.name(), world.time_of_day()));
+            }
+            (Some("daylength"), 2 | 3) => {
+                let Ok(day_length) = args[1].parse::<u32>() else {
+                    sender.send_error_message("The day length must be a positive integer".to_string());
+                    return;
+                };
+                world.set_day_length(day_length);
+                sender.send_message(format!("A day in {} now takes {} ticks", world.name(), world.day_length()));
+            }
+            (_, 0 | 1) => {
+                let day_length = world.day_length();
+                sender.send_message(format!("World {} has been running for {} ticks ({} days)", world.name(), world.time(), world.time() / day_length.max(1) as u64));
+                sender.send_message(format!(
+                    "Time of day: {}/{day_length} ({}, a day takes {} seconds)",
+                    world.time_of_day(),
+                    if world.is_day() { "day" } else { "night" },
+                    day_length / TPS as u32
+                ));
+            }
+            _ => sender.send_error_message(USAGE.to_string()),
+        }
+    }
+}
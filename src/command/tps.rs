@@ -0,0 +1,18 @@
+use crate::command::{CommandExecutor, CommandSender};
+use crate::server::TPS;
+use crate::FactoryIsland;
+
+pub struct TpsCommand;
+
+impl CommandExecutor for TpsCommand {
+    fn on_command(&mut self, sender: CommandSender, _: String, _: Vec<String>, fi: &mut FactoryIsland) {
+        let stats = &fi.stats;
+        let (players, worlds, packets) = stats.breakdown();
+        sender.send_message(format!("TPS: {:.2}/{TPS}", stats.tps()));
+        sender.send_message(format!("MSPT: {:.2} avg, {:.2} max", stats.mspt(), stats.max_mspt()));
+        sender.send_message(format!("Players: {players:.2}ms, worlds: {worlds:.2}ms, packets: {packets:.2}ms"));
+        if stats.skipped() > 0 {
+            sender.send_message(format!("Skipped {} ticks since the server started", stats.skipped()));
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use crate::command::{CommandExecutor, CommandSender};
+use crate::FactoryIsland;
+
+pub struct UpgradeCommand;
+
+impl CommandExecutor for UpgradeCommand {
+    fn on_command(&mut self, sender: CommandSender, _: String, _: Vec<String>, fi: &mut FactoryIsland) {
+        if let CommandSender::Player(_) = &sender {
+            sender.send_error_message("This command can only be ran through console".to_string());
+            return;
+        }
+
+        for world in fi.worlds.loaded() {
+            let mut world = world.lock();
+            if world.is_backing_up() {
+                sender.send_error_message(format!("A backup of {} is being written, try again once it is done", world.name()));
+                continue;
+            }
+            sender.send_message(format!("Upgrading the files of {} to the current version...", world.name()));
+            let upgraded = world.upgrade();
+            sender.send_message(format!("Upgraded {upgraded} files!"));
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::command::{CommandExecutor, CommandSender};
+use crate::world::generate::{GeneratorPreset, GeneratorSettings};
+use crate::world::worlds;
+use crate::world::World;
+use crate::{paths, FactoryIsland, PLAYERS};
+
+pub struct WorldCommand;
+
+const USAGE: &str = "Usage: world <list|info <name>|create <name> [seed] [preset]|delete <name> [confirm]|load <name>|unload <name>|tp <player> <world>>";
+
+impl WorldCommand {
+    fn list(sender: &CommandSender, fi: &FactoryIsland) {
+        let stored = worlds::stored_worlds();
+        if stored.is_empty() {
+            sender.send_message("There are no worlds".to_string());
+            return;
+        }
+        sender.send_message("Worlds:".to_string());
+        for name in stored {
+            //stored worlds are listed by key, the default name is a key as well
+            let state = if name == fi.worlds.default_name() {
+                " (default)"
+            } else if fi.worlds.is_loaded(&name) {
+                " (loaded)"
+            } else {
+                ""
+            };
+            sender.send_message(format!("{name}{state}"));
+        }
+    }
+
+    fn info(sender: &CommandSender, fi: &FactoryIsland, name: &str) {
+        let loaded = fi.worlds.get(name);
+        let (meta_name, seed, preset, forced, max_forced, loaded_chunks) = if let Some(world) = &loaded {
+            let lock = world.lock();
+            let meta = lock.meta();
+            (meta.name.clone(), meta.seed, meta.generator.preset, meta.forced_count(), meta.max_forced_chunks, Some(lock.loaded_chunk_count()))
+        } else if let Some(meta) = World::read_meta(name) {
+            (meta.name.clone(), meta.seed, meta.generator.preset, meta.forced_count(), meta.max_forced_chunks, None)
+        } else {
+            sender.send_error_message(format!("World {name} does not exist"));
+            return;
+        };
+        let size = paths::world_dir(name).map_or(0, |dir| worlds::size_on_disk(&dir));
+        sender.send_message(format!("Name: {meta_name}"));
+        sender.send_message(format!("Seed: {seed}"));
+        sender.send_message(format!("Preset: {}", preset.name()));
+        sender.send_message(format!("Forced chunks: {forced}/{max_forced}"));
+        sender.send_message(format!("Size on disk: {} KiB", size / 1024));
+        match loaded_chunks {
+            Some(count) => sender.send_message(format!("Loaded chunks: {count}")),
+            None => sender.send_message("Not loaded".to_string()),
+        }
+    }
+
+    fn create(sender: &CommandSender, fi: &mut FactoryIsland, args: &[String]) {
+        let mut seed = None;
+        let mut preset = GeneratorPreset::Default;
+        for arg in &args[2..] {
+            if let Ok(s) = arg.parse::<u32>() {
+                seed = Some(s);
+            } else if let Some(p) = GeneratorPreset::from_name(arg) {
+                preset = p;
+            } else {
+                let presets = GeneratorPreset::ALL.map(|p| p.name()).join(", ");
+                sender.send_error_message(format!("{arg} is neither a seed nor a preset, the presets are: {presets}"));
+                return;
+            }
+        }
+        match fi.worlds.create(&args[1], seed, GeneratorSettings::preset(preset)) {
+            Ok(_) => sender.send_message(format!("Created world {} with the {} preset!", args[1], preset.name())),
+            Err(e) => sender.send_error_message(e),
+        }
+    }
+
+    fn delete(sender: &CommandSender, fi: &mut FactoryIsland, name: &str, confirmed: bool) {
+        if !confirmed {
+            sender.send_message(format!("This deletes world {name} and cannot be undone, run `world delete {name} confirm` to continue"));
+            return;
+        }
+        match fi.worlds.delete(name) {
+            Ok(_) => sender.send_message(format!("Deleted world {name}!")),
+            Err(e) => sender.send_error_message(e),
+        }
+    }
+}
+
+impl CommandExecutor for WorldCommand {
+    fn on_command(&mut self, sender: CommandSender, _: String, args: Vec<String>, fi: &mut FactoryIsland) {
+        if let CommandSender::Player(_) = &sender {
+            sender.send_error_message("This command can only be ran through console".to_string());
+            return;
+        }
+
+        match (args.first().map(String::as_str), args.len()) {
+            (Some("list"), 1) => Self::list(&sender, fi),
+            (Some("info"), 2) => Self::info(&sender, fi, &args[1]),
+            (Some("create"), 2..=4) => Self::create(&sender, fi, &args),
+            (Some("delete"), 2) => Self::delete(&sender, fi, &args[1], false),
+            (Some("delete"), 3) if args[2] == "confirm" => Self::delete(&sender, fi, &args[1], true),
+            (Some("load"), 2) => {
+                if fi.worlds.is_loaded(&args[1]) {
+                    sender.send_error_message(format!("World {} is already loaded", args[1]));
+                    return;
+                }
+                match fi.worlds.load(&args[1]) {
+                    Ok(_) => sender.send_message(format!("Loaded world {}!", args[1])),
+                    Err(e) => sender.send_error_message(e),
+                }
+            }
+            (Some("unload"), 2) => {
+                match fi.unload_world(&args[1]) {
+                    Ok(_) => sender.send_message(format!("Unloaded world {}!", args[1])),
+                    Err(e) => sender.send_error_message(e),
+                }
+            }
+            (Some("tp"), 3) => {
+                let client_id = PLAYERS.read().iter()
+                    .find(|(_, p)| p.lock().name() == args[1])
+                    .map(|(id, _)| *id);
+                let Some(client_id) = client_id else {
+                    sender.send_error_message(format!("Player {} is not online", args[1]));
+                    return;
+                };
+                match fi.transfer_player(client_id, &args[2]) {
+                    Ok(_) => sender.send_message(format!("Moved {} to {}!", args[1], args[2])),
+                    Err(e) => sender.send_error_message(e),
+                }
+            }
+            _ => sender.send_error_message(USAGE.to_string()),
+        }
+    }
+}
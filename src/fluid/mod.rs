@@ -0,0 +1,176 @@
+use crate::ingredients::IngredientKind;
+use crate::registry::ingredients::INGREDIENT_REGISTRY;
+use crate::unit::parsing::parse_number_and_unit;
+use crate::unit::Unit;
+use crate::world::tiles::pos::TilePos;
+use crate::world::tiles::{Orientation, TileType};
+use crate::world::World;
+use bytebuffer::ByteBuffer;
+use hashbrown::HashMap;
+use log::warn;
+use mvutils::save::Savable;
+use mvutils::Savable;
+
+/// Fluid volume in liters.
+pub type Volume = f32;
+
+/// Fraction of the pressure difference between two connected containers that is equalized per tick.
+pub const FLOW_RATE: f32 = 0.5;
+/// Volumes below this are treated as empty to avoid fluids trickling forever.
+pub const MIN_VOLUME: Volume = 0.001;
+
+/// Parses a volume like `16kL` or `250 mL` into liters.
+pub fn volume(s: &str) -> Volume {
+    match parse_number_and_unit(s) {
+        Ok((value, unit @ Unit::Liters(_))) => unit.to_base(value),
+        _ => panic!("{s} is not a valid volume!"),
+    }
+}
+
+#[derive(Clone, Savable, PartialEq, Debug)]
+pub struct FluidStack {
+    pub ingredient: IngredientKind,
+    pub volume: Volume,
+}
+
+impl FluidStack {
+    pub fn new(ingredient: IngredientKind, volume: Volume) -> Self {
+        if !INGREDIENT_REGISTRY.reference_object(ingredient).is_some_and(|i| i.is_fluid()) {
+            warn!("Created a FluidStack with ingredient {ingredient} which is not a fluid!");
+        }
+        Self { ingredient, volume }
+    }
+}
+
+#[derive(Clone, Savable, Debug)]
+pub struct FluidTank {
+    capacity: Volume,
+    fluid: Option<FluidStack>,
+}
+
+impl FluidTank {
+    pub fn new(capacity: Volume) -> Self {
+        Self {
+            capacity,
+            fluid: None,
+        }
+    }
+
+    pub fn capacity(&self) -> Volume {
+        self.capacity
+    }
+
+    pub fn fluid(&self) -> Option<&FluidStack> {
+        self.fluid.as_ref()
+    }
+
+    pub fn volume(&self) -> Volume {
+        self.fluid.as_ref().map_or(0.0, |f| f.volume)
+    }
+
+    /// The pressure of the tank, which is just how full it is from 0 to 1.
+    pub fn pressure(&self) -> f32 {
+        self.volume() / self.capacity
+    }
+
+    /// Fill level in percent, used to decide when the client needs an update.
+    pub fn fill_percent(&self) -> u8 {
+        (self.pressure() * 100.0).round() as u8
+    }
+
+    pub fn can_accept(&self, ingredient: IngredientKind) -> bool {
+        self.fluid.as_ref().is_none_or(|f| f.ingredient == ingredient)
+    }
+
+    /// Fills up to `amount` of the fluid into the tank and returns how much was actually accepted.
+    pub fn fill(&mut self, ingredient: IngredientKind, amount: Volume) -> Volume {
+        if !self.can_accept(ingredient) || amount <= 0.0 {
+            return 0.0;
+        }
+        let accepted = amount.min(self.capacity - self.volume());
+        match &mut self.fluid {
+            Some(fluid) => fluid.volume += accepted,
+            None => self.fluid = Some(FluidStack::new(ingredient, accepted)),
+        }
+        accepted
+    }
+
+    /// Drains up to `amount` out of the tank.
+    pub fn drain(&mut self, amount: Volume) -> Option<FluidStack> {
+        let fluid = self.fluid.as_mut()?;
+        let drained = amount.min(fluid.volume);
+        fluid.volume -= drained;
+        let ingredient = fluid.ingredient;
+        if fluid.volume < MIN_VOLUME {
+            self.fluid = None;
+        }
+        Some(FluidStack { ingredient, volume: drained })
+    }
+
+    /// Moves fluid between two connected tanks so that their pressures move towards each other.
+    pub fn exchange(a: &mut FluidTank, b: &mut FluidTank) {
+        let ingredient = match (&a.fluid, &b.fluid) {
+            (Some(fa), Some(fb)) if fa.ingredient != fb.ingredient => return,
+            (Some(f), _) | (_, Some(f)) => f.ingredient,
+            (None, None) => return,
+        };
+        // volume that would have to move from a to b for both to have the same pressure
+        let equal = (a.volume() * b.capacity - b.volume() * a.capacity) / (a.capacity + b.capacity);
+        let amount = equal * FLOW_RATE;
+        if amount.abs() < MIN_VOLUME {
+            return;
+        }
+        let (from, to) = if amount > 0.0 { (a, b) } else { (b, a) };
+        if let Some(drained) = from.drain(amount.abs()) {
+            let accepted = to.fill(ingredient, drained.volume);
+            // cannot really happen as we never move more than the receiver has space for, but be safe
+            if accepted < drained.volume {
+                from.fill(ingredient, drained.volume - accepted);
+            }
+        }
+    }
+
+    pub fn save_client_state(&self, saver: &mut ByteBuffer) {
+        self.fluid.as_ref().map(|f| f.ingredient).save(saver);
+        self.pressure().save(saver);
+    }
+}
+
+/// Runs one tick of fluid flow between all connected fluid tiles in `tiles` and syncs the ones whose fill level visibly changed.
+/// Chunks of tanks whose volume changed at all are marked dirty.
+pub fn simulate(world: &mut World, tiles: &[(TileType, TilePos)]) {
+    let mut containers = HashMap::new();
+    for (tile, pos) in tiles {
+        let lock = tile.read();
+        if let Some(tank) = lock.instance.fluid_tank() {
+            containers.insert(pos.raw, (tile.clone(), pos.clone(), (tank.fill_percent(), tank.volume())));
+        }
+    }
+
+    for (tile, pos, _) in containers.values() {
+        // only look east and north so that every pair is handled once
+        for side in [Orientation::East, Orientation::North] {
+            let neighbour = pos.step(side, 1);
+            if let Some((other, _, _)) = containers.get(&neighbour.raw) {
+                let mut a = tile.write();
+                let mut b = other.write();
+                if !a.instance.connects_fluid(side) || !b.instance.connects_fluid(side.opposite()) {
+                    continue;
+                }
+                if let (Some(ta), Some(tb)) = (a.instance.fluid_tank_mut(), b.instance.fluid_tank_mut()) {
+                    FluidTank::exchange(ta, tb);
+                }
+            }
+        }
+    }
+
+    for (tile, pos, (percent, volume)) in containers.into_values() {
+        let after = tile.read().instance.fluid_tank().map(|tank| (tank.fill_percent(), tank.volume()));
+        if after.is_none_or(|(_, after)| after != volume) {
+            world.mark_dirty(pos.chunk_pos);
+        }
+        if after.is_none_or(|(after, _)| after != percent) {
+            world.sync_tilestate(pos);
+        }
+    }
+}
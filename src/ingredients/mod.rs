@@ -132,6 +132,20 @@ impl IngredientCreator {
     }
 }
 
+impl Ingredient {
+    pub fn kind(&self) -> IngredientKind {
+        self.kind
+    }
+
+    pub fn static_meta(&self) -> &Meta {
+        &self.static_meta
+    }
+
+    pub fn is_fluid(&self) -> bool {
+        matches!(self.static_meta.get("state"), Some(MetaField { value: MetaValue::Str(s), .. }) if s == "fluid")
+    }
+}
+
 impl Registerable for Ingredient {
     type CreateInfo = IngredientCreator;
 
@@ -5,10 +5,13 @@ use crate::command::{CommandProcessor, CommandSender, COMMAND_PROCESSOR};
 use crate::player::{Player, PlayerType};
 use crate::registry::terrain::TerrainTiles;
 use crate::registry::GameObjects;
-use crate::server::packets::common::{ClientDataPacket, PlayerData, ServerStatePacket};
+use crate::server::packets::common::{ClientDataPacket, PlayerData, ServerStatePacket, ServerStoppingPacket};
 use crate::server::packets::player::{OtherPlayerChatPacket, OtherPlayerJoinPacket, OtherPlayerLeavePacket, OtherPlayerMovePacket, PlayerMovePacket};
-use crate::server::{ClientBoundPacket, ServerBoundPacket, REQ_WORLD};
-use crate::world::{TileSetReason, World, WorldType};
+use crate::server::{ClientBoundPacket, ServerBoundPacket, REQ_GENERATOR, REQ_SETTINGS, REQ_WORLD, TPS};
+use crate::server::stats::{TickStats, TickTimings};
+use crate::settings::GameSettings;
+use crate::world::worlds::WorldManager;
+use crate::world::{backup, ChunkPos, TileSetReason, World, WorldType};
 use hashbrown::HashSet;
 use log::{debug, error, info, warn};
 use mvengine::event::EventBus;
@@ -20,9 +23,8 @@ use mvutils::{enum_val, lazy};
 use mvutils::hashers::U64IdentityHasher;
 use std::collections::HashMap;
 use std::ops::Deref;
-use std::process::exit;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{env, thread};
 use std::sync::atomic::{AtomicBool, Ordering};
 use abi_stable::traits::IntoReprC;
@@ -47,12 +49,14 @@ pub mod server;
 pub mod command;
 pub mod registry;
 pub mod ingredients;
+pub mod fluid;
 pub mod multitile;
 pub mod packethandler;
 pub mod inventory;
 pub mod unit;
 mod utils;
 pub mod meta;
+pub mod paths;
 
 lazy! {
     pub(crate) static PLAYERS: RwLock<HashMap<ClientId, PlayerType, U64IdentityHasher>> = RwLock::new(HashMap::with_hasher(U64IdentityHasher::default()));
@@ -68,49 +72,254 @@ pub fn broadcast_all_players(packet: ClientBoundPacket) {
     }
 }
 
+/// Sends the packet to every player in the world with the given [`World::key`].
+pub fn broadcast_world(world: &str, packet: ClientBoundPacket) {
+    let players = PLAYERS.read();
+    for player in players.values() {
+        let lock = player.lock();
+        if lock.world_name() != world {
+            continue;
+        }
+        if let Some(endpoint) = lock.client_endpoint() {
+            endpoint.send(packet.clone());
+        }
+    }
+}
+
 pub struct FactoryIsland {
-    pub(crate) world: WorldType,
+    pub(crate) worlds: WorldManager,
     pub(crate) render_distance: i32,
+    pub(crate) settings: GameSettings,
+    ticks_since_autosave: u32,
+    ticks_since_backup: u32,
+    pub(crate) stats: TickStats,
+    stop_reason: Option<String>,
+    stopped: bool,
     
     pub objects: GameObjects,
 }
 
 impl FactoryIsland {
     pub fn tick(&mut self) {
-        let mut loaded_by_player = HashSet::new();
+        let start = Instant::now();
+        let mut timings = TickTimings::default();
+        let worlds = self.worlds.loaded();
+        for world in &worlds {
+            world.lock().poll_chunks();
+        }
+
+        let autosave_ticks = self.settings.autosave_interval * TPS as u32;
+        self.ticks_since_autosave += 1;
+        let autosave = autosave_ticks > 0 && self.ticks_since_autosave >= autosave_ticks;
+        if autosave {
+            self.ticks_since_autosave = 0;
+        }
+
+        let backup_ticks = self.settings.backup_interval * TPS as u32;
+        self.ticks_since_backup += 1;
+        let backup = backup_ticks > 0 && self.ticks_since_backup >= backup_ticks;
+        if backup {
+            self.ticks_since_backup = 0;
+        }
+
+        let mut loaded_by_player: HashMap<String, HashSet<ChunkPos>> = HashMap::new();
+        let mut simulated_by_player: HashMap<String, HashSet<ChunkPos>> = HashMap::new();
+        let players_start = Instant::now();
         let mut player_lock = PLAYERS.write();
         for player in player_lock.values() {
             let mut lock = player.lock();
             lock.tick();
-            for pos in &lock.loaded_chunks {
-                loaded_by_player.insert(*pos);
+            lock.deliver_pending_chunks();
+            if autosave || backup {
+                lock.save();
+            }
+            let loaded = loaded_by_player.entry(lock.world_name().to_string()).or_default();
+            for pos in lock.loaded_chunks.iter().chain(&lock.pending_chunks) {
+                loaded.insert(*pos);
+            }
+            let distance = self.settings.simulation_distance.min(lock.data.render_distance);
+            let (cx, cz) = lock.get_current_chunk();
+            let simulated = simulated_by_player.entry(lock.world_name().to_string()).or_default();
+            for x in (cx - distance)..=(cx + distance) {
+                for z in (cz - distance)..=(cz + distance) {
+                    simulated.insert((x, z));
+                }
             }
         }
         drop(player_lock);
-        let mut world = self.world.lock();
-        world.check_unload(loaded_by_player);
-        world.tick();
-        
-        drop(world);
+        timings.players = players_start.elapsed();
+
+        let worlds_start = Instant::now();
+        for world in worlds {
+            let mut world = world.lock();
+            let keep = loaded_by_player.remove(world.key()).unwrap_or_default();
+            let simulated = simulated_by_player.remove(world.key()).unwrap_or_default();
+            world.check_unload(keep);
+            world.tick(&simulated);
+            if autosave {
+                world.begin_autosave();
+            }
+            world.autosave_step(self.settings.autosave_chunks_per_tick);
+            if backup {
+                match backup::start_backup(&mut world, self.settings.backup_retention) {
+                    Ok(true) => info!("Started scheduled backup of {}", world.name()),
+                    Ok(false) => warn!("Skipping scheduled backup of {}, the previous one is still being written", world.name()),
+                    Err(e) => error!("Scheduled backup of {} failed: {e}", world.name()),
+                }
+            }
+            drop(world);
+        }
+        timings.worlds = worlds_start.elapsed();
+        timings.total = start.elapsed();
+        self.stats.record(start, timings);
+    }
+
+    /// Moves the player into another loaded world. Players of the old world see them leave, players of the new one see them join.
+    pub fn transfer_player(&mut self, client_id: ClientId, world_name: &str) -> Result<(), String> {
+        let world = self.worlds.get(world_name).ok_or_else(|| format!("World {world_name} is not loaded"))?;
+        let world_name = paths::world_key(world_name)?;
+        let players = PLAYERS.write();
+        let player = players.get(&client_id).ok_or_else(|| "Player is not online".to_string())?;
+        let mut lock = player.lock();
+        let old_world = lock.world_name().to_string();
+        if old_world == world_name {
+            return Ok(());
+        }
+        lock.change_world(world, &world_name);
+        let data = lock.data.clone();
+        let endpoint = lock.client_endpoint().cloned();
+        drop(lock);
+
+        for (other_id, other_player) in players.iter().filter(|(p, _)| **p != client_id) {
+            let other = other_player.lock();
+            let in_old = other.world_name() == old_world;
+            let in_new = other.world_name() == world_name;
+            if let Some(other_endpoint) = other.client_endpoint() {
+                if in_old {
+                    other_endpoint.send(ClientBoundPacket::OtherPlayerLeave(OtherPlayerLeavePacket {
+                        client_id,
+                    }));
+                } else if in_new {
+                    other_endpoint.send(ClientBoundPacket::OtherPlayerJoin(OtherPlayerJoinPacket {
+                        client_id,
+                        client_data: data.clone(),
+                    }));
+                }
+            }
+            if let Some(endpoint) = &endpoint {
+                if in_old {
+                    endpoint.send(ClientBoundPacket::OtherPlayerLeave(OtherPlayerLeavePacket {
+                        client_id: *other_id,
+                    }));
+                } else if in_new {
+                    endpoint.send(ClientBoundPacket::OtherPlayerJoin(OtherPlayerJoinPacket {
+                        client_id: *other_id,
+                        client_data: other.data.clone(),
+                    }));
+                    endpoint.send(ClientBoundPacket::OtherPlayerMove(OtherPlayerMovePacket {
+                        client_id: *other_id,
+                        pos: other.position,
+                    }));
+                }
+            }
+        }
+        info!("Moved {} from {old_world} to {world_name}", data.profile.name);
+        Ok(())
+    }
+
+    /// Moves everyone in the world to the default world and unloads it.
+    pub fn unload_world(&mut self, world_name: &str) -> Result<(), String> {
+        let world_name = paths::world_key(world_name)?;
+        if world_name == self.worlds.default_name() {
+            return Err("The default world cannot be unloaded".to_string());
+        }
+        let default = self.worlds.default_name().to_string();
+        let in_world = PLAYERS.read().iter()
+            .filter(|(_, p)| p.lock().world_name() == world_name)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in in_world {
+            self.transfer_player(id, &default)?;
+        }
+        self.worlds.unload(&world_name)
+    }
+
+    fn handle_packet(&mut self, client: Arc<ClientEndpoint>, packet: ServerBoundPacket) {
+        let world = self.player_world(client.id()).unwrap_or_else(|| self.worlds.default_world());
+        let mut world_lock = world.lock();
+        if let Some(packet) = world_lock.check_packet(packet, &client) {
+            drop(world_lock);
+            if let Some(_) = PacketHandler::check_packet(packet, &client, self) {
+                warn!("Couldnt handle packet!");
+            }
+        }
+    }
+
+    /// The world a player is currently in.
+    pub fn player_world(&self, client_id: ClientId) -> Option<WorldType> {
+        let players = PLAYERS.read();
+        let player = players.get(&client_id)?;
+        let lock = player.lock();
+        lock.world().cloned()
     }
 
     pub fn on_command(&mut self, command: String, player: Option<PlayerData>) {
         COMMAND_PROCESSOR.process(player.map_or(CommandSender::Console, |d| CommandSender::Player(d)), command, self);
     }
 
-    pub fn stop(&mut self) {
-        exit(0);
+    /// Asks the server to shut down, the tick loop picks this up and calls [`FactoryIsland::shutdown`].
+    pub fn stop(&mut self, reason: &str) {
+        if self.stop_reason.is_none() {
+            self.stop_reason = Some(reason.to_string());
+        }
     }
-    
+
+    pub fn is_stopping(&self) -> bool {
+        self.stop_reason.is_some()
+    }
+
+    /// Saves all players and worlds without disconnecting anyone.
     pub fn save(&self) {
-        let mut world = self.world.lock();
-        world.save();
-        drop(world);
-        let players = PLAYERS.read();
+        for player in PLAYERS.read().values() {
+            player.lock().save();
+        }
+        self.worlds.save_all();
+    }
+
+    /// Tells every player why the server stops, saves and disconnects them and saves all worlds. Only runs once.
+    pub(crate) fn shutdown(&mut self) {
+        if self.stopped {
+            return;
+        }
+        self.stopped = true;
+        let reason = self.stop_reason.clone().unwrap_or_else(|| "Server closed".to_string());
+        info!("Stopping server: {reason}");
+
+        let mut players = PLAYERS.write();
+        let mut endpoints = Vec::new();
         for player in players.values() {
-            let mut locked = player.lock();
-            locked.on_disconnect();
+            let mut lock = player.lock();
+            if let Some(endpoint) = lock.client_endpoint() {
+                endpoint.send(ClientBoundPacket::ServerStopping(ServerStoppingPacket {
+                    reason: reason.clone(),
+                }));
+                endpoints.push(endpoint.clone());
+            }
+            lock.on_disconnect();
+        }
+        players.clear();
+        drop(players);
+        //the players are gone already, so the disconnect callbacks have nothing left to do
+        for endpoint in endpoints {
+            endpoint.disconnect(DisconnectReason::Disconnected);
+        }
+
+        //a save during a backup is put off until it is done, which would be too late here
+        for world in self.worlds.loaded() {
+            world.lock().wait_for_backup();
         }
+        self.worlds.save_all();
+        info!("Saved all players and worlds");
     }
 }
 
@@ -120,6 +329,10 @@ impl ServerHandler<ServerBoundPacket> for FactoryIsland {
         let tiles = registry::tiles::register_all();
         let ingredients = registry::ingredients::register_all();
         let multitiles = registry::multitiles::register_all(&tiles);
+        let deposits = registry::deposits::register_all(&ingredients);
+        let stages = registry::stages::register_all();
+        let biomes = registry::biomes::register_all(&terrain_tiles, &deposits);
+        let structures = registry::structures::register_all(&terrain_tiles, &tiles, &multitiles, &biomes);
         command::register_commands();
         
         let objects = GameObjects {
@@ -127,6 +340,10 @@ impl ServerHandler<ServerBoundPacket> for FactoryIsland {
             tiles,
             ingredients,
             multitiles,
+            deposits,
+            stages,
+            biomes,
+            structures,
         };
 
         let stack = IngredientStack::new(objects.ingredients.stone, 1);
@@ -135,15 +352,20 @@ impl ServerHandler<ServerBoundPacket> for FactoryIsland {
         let bind = REQ_WORLD.read();
         let world_name = bind.as_ref();
         let world_name = world_name.map(|x| x.as_str()).unwrap_or("main");
+        let generator = REQ_GENERATOR.read().clone();
+        let settings = REQ_SETTINGS.read().clone();
 
-        let world = World::load(world_name, objects.clone());
-
-        //idk maybe panic here instead
-        let world = world.unwrap_or(World::get_main(objects.clone()));
+        let world = World::get_or_create(world_name, generator, objects.clone());
 
         FactoryIsland {
-            world,
-            render_distance: 1,
+            worlds: WorldManager::new(world, objects.clone()),
+            render_distance: settings.render_distance,
+            settings,
+            ticks_since_autosave: 0,
+            ticks_since_backup: 0,
+            stats: TickStats::new(),
+            stop_reason: None,
+            stopped: false,
             
             objects,
         }
@@ -153,8 +375,12 @@ impl ServerHandler<ServerBoundPacket> for FactoryIsland {
         debug!("client connect call");
         let mut player_data = Vec::new();
         let mut players = PLAYERS.write();
+        //new players join the default world, so only the players in there are visible to them
         for (client_id, player) in players.iter() {
             let lock = player.lock();
+            if lock.world_name() != self.worlds.default_name() {
+                continue;
+            }
             let data = PlayerData {
                 client_id: *client_id,
                 data: lock.data.clone(),
@@ -180,7 +406,7 @@ impl ServerHandler<ServerBoundPacket> for FactoryIsland {
             client_id: client.id(),
         }));
         let id = client.id();
-        let player = Player::new(client, self.world.clone());
+        let player = Player::new(client, self.worlds.default_world());
         players.insert(id, player);
     }
 
@@ -206,16 +432,13 @@ impl ServerHandler<ServerBoundPacket> for FactoryIsland {
     }
 
     fn on_packet(&mut self, client: Arc<ClientEndpoint>, packet: ServerBoundPacket) {
-        let mut world_lock = self.world.lock();
-        if let Some(packet) = world_lock.check_packet(packet, &client) {
-            drop(world_lock);
-            if let Some(_) = PacketHandler::check_packet(packet, &client, self) {
-                warn!("Couldnt handle packet!");
-            }
-        }
+        let start = Instant::now();
+        self.handle_packet(client, packet);
+        self.stats.add_packet_time(start.elapsed());
     }
 
     fn on_server_stop(&mut self, message: &str) {
-
+        self.stop(message);
+        self.shutdown();
     }
 }
\ No newline at end of file
@@ -20,11 +20,16 @@ impl PacketHandler {
                 if let Some(player) = players.get(&client.id()) {
                     let mut lock = player.lock();
                     lock.apply_data(packet.clone());
+                    let world = lock.world_name().to_string();
+                    drop(lock);
 
                     let id = client.id();
                     debug!("starting client join message");
                     for (_, other_player) in players.iter().filter(|(p, _)| **p != id) {
                         let lock = other_player.lock();
+                        if lock.world_name() != world {
+                            continue;
+                        }
                         if let Some(endpoint) = lock.client_endpoint() {
                             endpoint.send(ClientBoundPacket::OtherPlayerJoin(OtherPlayerJoinPacket {
                                 client_id: id,
@@ -43,10 +48,14 @@ impl PacketHandler {
 
                     let mut lock = player.lock();
                     lock.move_to(pos);
+                    let world = lock.world_name().to_string();
                     drop(lock);
 
                     for (_, other_player) in players.iter().filter(|(p, _)| **p != client.id()) {
                         let lock = other_player.lock();
+                        if lock.world_name() != world {
+                            continue;
+                        }
                         if let Some(endpoint) = lock.client_endpoint() {
                             endpoint.send(ClientBoundPacket::OtherPlayerMove(OtherPlayerMovePacket {
                                 client_id: client.id(),
@@ -0,0 +1,87 @@
+use mvutils::lazy;
+use parking_lot::RwLock;
+use std::env;
+use std::path::{Component, Path, PathBuf};
+use crate::world::manager;
+
+/// Environment variable that overrides where the server stores its data.
+pub const ROOT_ENV: &str = "FACTORYISLAND_HOME";
+/// Command line flag that overrides where the server stores its data, as `--data-dir <path>` or `--data-dir=<path>`.
+pub const ROOT_FLAG: &str = "--data-dir";
+
+lazy! {
+    static ROOT_OVERRIDE: RwLock<Option<PathBuf>> = RwLock::new(None);
+}
+
+/// Makes all data go to `path`, taking precedence over everything else.
+/// Meant for embedders that have their own config, and for pointing tests at a temporary directory.
+pub fn set_storage_root(path: impl Into<PathBuf>) {
+    *ROOT_OVERRIDE.write() = Some(path.into());
+}
+
+pub fn clear_storage_root() {
+    *ROOT_OVERRIDE.write() = None;
+}
+
+fn root_from_args() -> Option<PathBuf> {
+    let mut args = env::args();
+    while let Some(arg) = args.next() {
+        if arg == ROOT_FLAG {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix(ROOT_FLAG).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+fn non_empty_var(name: &str) -> Option<PathBuf> {
+    env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from)
+}
+
+/// The data directory the platform expects applications to use. `data_dir` of the mod packer mirrors this.
+fn platform_root() -> Option<PathBuf> {
+    if cfg!(windows) {
+        non_empty_var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        non_empty_var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        non_empty_var("XDG_DATA_HOME").or_else(|| non_empty_var("HOME").map(|home| home.join(".local/share")))
+    }
+}
+
+/// Resolves the directory all server data lives in. In order: [`set_storage_root`], the [`ROOT_FLAG`] command line flag,
+/// the [`ROOT_ENV`] environment variable, the platform data directory and as a last resort the working directory.
+pub fn storage_root() -> PathBuf {
+    if let Some(root) = ROOT_OVERRIDE.read().clone() {
+        return root;
+    }
+    root_from_args()
+        .or_else(|| non_empty_var(ROOT_ENV))
+        .or_else(platform_root)
+        .unwrap_or_else(|| env::current_dir().unwrap_or_default())
+}
+
+/// The directory containing all worlds.
+pub fn worlds_dir() -> PathBuf {
+    storage_root().join(manager::PATH)
+}
+
+/// What a world is looked up by, its name with spaces replaced. This is also the name of its directory, so
+/// "My World" and "My_World" are the same world. Names that are empty or would lead out of the worlds directory are
+/// rejected.
+pub fn world_key(name: &str) -> Result<String, String> {
+    let key = name.replace(' ', "_");
+    let mut components = Path::new(&key).components();
+    let single = matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none();
+    if !single || key.contains(['/', '\\']) {
+        return Err(format!("{name:?} is not a valid world name, it must not be empty, contain path separators or be .."));
+    }
+    Ok(key)
+}
+
+/// The directory of a single world, see [`world_key`].
+pub fn world_dir(name: &str) -> Result<PathBuf, String> {
+    Ok(worlds_dir().join(world_key(name)?))
+}
@@ -5,7 +5,9 @@ use std::ops::{Deref, DerefMut};
 use crate::server::packets::common::ClientDataPacket;
 use crate::server::packets::world::{ChunkDataPacket, ChunkUnloadPacket};
 use crate::server::ClientBoundPacket;
-use crate::world::{ChunkPos, SingleTileUnit, TileUnit, WorldType, CHUNK_SIZE};
+use crate::world::storage;
+use crate::world::versioning::SaveKind;
+use crate::world::{ChunkPos, ChunkType, SingleTileUnit, TileUnit, WorldType, CHUNK_SIZE};
 use hashbrown::HashSet;
 use mvengine::net::server::ClientEndpoint;
 use mvengine::utils::savers::SaveArc;
@@ -40,20 +42,29 @@ pub struct Player {
     client_endpoint: Option<Arc<ClientEndpoint>>,
     #[unsaved]
     world: Option<WorldType>,
+    /// Name of the world the player is in, kept here so it can be checked while the world is locked
+    #[unsaved]
+    world_name: String,
     pub position: TileUnit,
     #[unsaved]
     pub loaded_chunks: HashSet<ChunkPos>,
+    /// Chunks that are being loaded in the background and will be sent once they are ready
+    #[unsaved]
+    pub pending_chunks: HashSet<ChunkPos>,
     pub reach: SingleTileUnit
 }
 
 impl Player {
     pub fn new(endpoint: Arc<ClientEndpoint>, world: WorldType) -> SaveArc<Mutex<Self>> {
+        let world_name = world.lock().key().to_string();
         let this = Self {
             data: ClientDataPacket::default(),
             client_endpoint: Some(endpoint),
             world: Some(world),
+            world_name,
             position: (0.0, 0.0),
             loaded_chunks: HashSet::new(),
+            pending_chunks: HashSet::new(),
             reach: 7.0,
         };
         SaveArc::new(Mutex::new(this))
@@ -63,6 +74,37 @@ impl Player {
 
     }
 
+    fn send_chunk(&self, pos: ChunkPos, chunk: &ChunkType) {
+        let chunk = chunk.lock();
+        let data_packet = ChunkDataPacket {
+            pos,
+            data: chunk.to_client(),
+        };
+        if let Some(client_endpoint) = &self.client_endpoint {
+            client_endpoint.send(ClientBoundPacket::ChunkData(data_packet));
+        }
+    }
+
+    /// Sends all pending chunks that finished loading in the meantime.
+    pub(crate) fn deliver_pending_chunks(&mut self) {
+        if self.pending_chunks.is_empty() {
+            return;
+        }
+        if let Some(world) = &self.world {
+            let mut world_lock = world.lock();
+            for pos in self.pending_chunks.clone() {
+                if let Some(chunk) = world_lock.get_loaded_chunk(pos) {
+                    self.send_chunk(pos, &chunk);
+                    self.pending_chunks.remove(&pos);
+                    self.loaded_chunks.insert(pos);
+                } else if !world_lock.is_pending(pos) {
+                    //the request was dropped, for example because the chunk got unloaded before it was ready
+                    world_lock.request_chunk(pos);
+                }
+            }
+        }
+    }
+
     pub(crate) fn after_move(&mut self, render_distance: i32) {
         if let Some(world) = &self.world {
             let current_chunk = self.get_current_chunk();
@@ -70,21 +112,21 @@ impl Player {
             let mut to_unload = self.loaded_chunks.clone();
             for chunk_x in (current_chunk.0 - render_distance)..=(current_chunk.0 + render_distance) {
                 for chunk_y in (current_chunk.1 - render_distance)..=(current_chunk.1 + render_distance) {
-                    if !self.loaded_chunks.contains(&(chunk_x, chunk_y)) {
-                        let chunk = world_lock.get_chunk((chunk_x, chunk_y));
-                        let chunk = chunk.lock();
-                        let data_packet = ChunkDataPacket {
-                            pos: (chunk_x, chunk_y),
-                            data: chunk.to_client(),
-                        };
-                        if let Some(client_endpoint) = &self.client_endpoint {
-                            client_endpoint.send(ClientBoundPacket::ChunkData(data_packet));
+                    let pos = (chunk_x, chunk_y);
+                    if !self.loaded_chunks.contains(&pos) && !self.pending_chunks.contains(&pos) {
+                        if let Some(chunk) = world_lock.request_chunk(pos) {
+                            self.send_chunk(pos, &chunk);
+                            self.loaded_chunks.insert(pos);
+                        } else {
+                            self.pending_chunks.insert(pos);
                         }
-                        self.loaded_chunks.insert((chunk_x, chunk_y));
                     }
-                    to_unload.remove(&(chunk_x, chunk_y));
+                    to_unload.remove(&pos);
                 }
             }
+            self.pending_chunks.retain(|pos| {
+                (current_chunk.0 - pos.0).abs() <= render_distance && (current_chunk.1 - pos.1).abs() <= render_distance
+            });
             for pos in to_unload {
                 if (current_chunk.0 - pos.0).abs() < render_distance + UNLOAD_DISTANCE {
                     if (current_chunk.1 - pos.1).abs() < render_distance + UNLOAD_DISTANCE {
@@ -103,12 +145,15 @@ impl Player {
 
     pub(crate) fn on_disconnect(&mut self) {
         self.loaded_chunks.clear();
-        //save player to file
+        self.pending_chunks.clear();
+        self.save();
+    }
+
+    /// Saves the player to its file in the world it is in.
+    pub(crate) fn save(&self) {
         if let Some(world) = &self.world {
             let lock = world.lock();
-            let dir = lock.players_directory();
-            let filename = format!("{:?}.sav", self.data.profile.uuid);
-            dir.save_object(self, &filename);
+            storage::write_object(&lock.player_file(&self.data.profile.uuid), SaveKind::Player, self);
         }
     }
 
@@ -125,14 +170,20 @@ impl Player {
 
     pub fn apply_data(&mut self, packet: ClientDataPacket) {
         self.data = packet;
+        self.load_from_world();
+    }
+
+    /// Reads the position of the player in the current world and sends them the chunks around it.
+    fn load_from_world(&mut self) {
         if let Some(world) = &self.world {
             let lock = world.lock();
-            let filename = format!("{:?}.sav", self.data.profile.uuid);
-            let players_dir = lock.players_directory();
-            if let Some(t) = players_dir.read_object::<Player>(&filename) {
+            if let Some(t) = storage::read_object::<Player>(&lock.player_file(&self.data.profile.uuid), SaveKind::Player) {
                 self.position = t.position;
                 self.reach = t.reach;
             }
+            if let Some(endpoint) = &self.client_endpoint {
+                endpoint.send(lock.time_packet());
+            }
         }
 
         self.after_move(self.data.render_distance);
@@ -144,24 +195,27 @@ impl Player {
             }));
         }
 
-        if let Some(world) = &self.world {
-            let mut world_lock = world.lock();
-            let current_chunk = self.get_current_chunk();
-            for chunk_x in (current_chunk.0 - self.data.render_distance)..=(current_chunk.0 + self.data.render_distance) {
-                for chunk_y in (current_chunk.1 - self.data.render_distance)..=(current_chunk.1 + self.data.render_distance) {
-                    let chunk = world_lock.get_chunk((chunk_x, chunk_y));
-                    let chunk = chunk.lock();
-                    let data_packet = ChunkDataPacket {
-                        pos: (chunk_x, chunk_y),
-                        data: chunk.to_client(),
-                    };
-                    if let Some(client_endpoint) = &self.client_endpoint {
-                        client_endpoint.send(ClientBoundPacket::ChunkData(data_packet));
-                    }
-                    self.loaded_chunks.insert((chunk_x, chunk_y));
-                }
+        //send everything again now that the client knows where it is, chunks that are not ready yet follow in the tick
+        self.loaded_chunks.clear();
+        self.after_move(self.data.render_distance);
+    }
+
+    /// Saves the player in the world they are leaving and puts them where they were last in the new world.
+    pub(crate) fn change_world(&mut self, world: WorldType, name: &str) {
+        self.save();
+        if let Some(client) = &self.client_endpoint {
+            for pos in self.loaded_chunks.drain() {
+                client.send(ClientBoundPacket::ChunkUnload(ChunkUnloadPacket {
+                    pos,
+                }));
             }
         }
+        self.loaded_chunks.clear();
+        self.pending_chunks.clear();
+        self.world = Some(world);
+        self.world_name = name.to_string();
+        self.position = (0.0, 0.0);
+        self.load_from_world();
     }
 
     pub fn name(&self) -> &str {
@@ -176,6 +230,10 @@ impl Player {
         self.world.as_ref()
     }
 
+    pub fn world_name(&self) -> &str {
+        &self.world_name
+    }
+
     pub fn get_current_chunk(&self) -> ChunkPos {
         (
             (self.position.0 / CHUNK_SIZE as f64).floor() as i32,
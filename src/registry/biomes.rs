@@ -0,0 +1,50 @@
+use mvutils::lazy;
+use crate::registry::deposits::Deposits;
+use crate::registry::terrain::TerrainTiles;
+use crate::registry::Registry;
+use crate::world::generate::biome::{Biome, BiomeCreateInfo};
+
+lazy! {
+    pub static BIOME_REGISTRY: Registry<Biome> = Registry::new();
+}
+
+macro_rules! define_biomes {
+    ($struct_name:ident, $func_name:ident, $terrain_ident:ident, $deposit_ident:ident => [$($biome_name:ident = $biome_init:expr),* $(,)?]) => {
+        #[derive(Clone)]
+        pub struct $struct_name {
+            $(pub $biome_name: usize),*
+        }
+
+        impl $struct_name {
+            pub fn by_name(&self, name: &str) -> Option<usize> {
+                match name {
+                    $(stringify!($biome_name) => Some(self.$biome_name),)*
+                    _ => None
+                }
+            }
+        }
+
+        pub fn $func_name($terrain_ident: &TerrainTiles, $deposit_ident: &Deposits) -> $struct_name {
+            $struct_name {
+                $(
+                    $biome_name: BIOME_REGISTRY.register($biome_init),
+                )*
+            }
+        }
+    };
+}
+
+define_biomes!(Biomes, register_all, t, d => [
+    plains = BiomeCreateInfo::new("plains", 0.5, 0.5,
+        &[(0.3, t.water), (0.36, t.sand), (0.75, t.grass), (1.0, t.stone)],
+        &[d.stone, d.coal, d.iron_ore]),
+    desert = BiomeCreateInfo::new("desert", 0.85, 0.15,
+        &[(0.25, t.water), (0.8, t.sand), (1.0, t.stone)],
+        &[d.stone, d.iron_ore]),
+    highlands = BiomeCreateInfo::new("highlands", 0.2, 0.4,
+        &[(0.3, t.water), (0.33, t.sand), (0.5, t.grass), (1.0, t.stone)],
+        &[d.stone, d.coal, d.iron_ore]),
+    wetlands = BiomeCreateInfo::new("wetlands", 0.6, 0.85,
+        &[(0.42, t.water), (0.46, t.sand), (1.0, t.grass)],
+        &[d.coal]),
+]);
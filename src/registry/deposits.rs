@@ -0,0 +1,31 @@
+use mvutils::lazy;
+use crate::registry::ingredients::Ingredients;
+use crate::registry::Registry;
+use crate::world::deposit::{DepositCreateInfo, DepositType, DepositYield};
+
+lazy! {
+    pub static DEPOSIT_REGISTRY: Registry<DepositType> = Registry::new();
+}
+
+macro_rules! define_deposits {
+    ($struct_name:ident, $func_name:ident, $ing_ident:ident => [$($deposit_name:ident = $deposit_init:expr),* $(,)?]) => {
+        #[derive(Clone)]
+        pub struct $struct_name {
+            $(pub $deposit_name: usize),*
+        }
+
+        pub fn $func_name($ing_ident: &Ingredients) -> $struct_name {
+            $struct_name {
+                $(
+                    $deposit_name: DEPOSIT_REGISTRY.register($deposit_init),
+                )*
+            }
+        }
+    };
+}
+
+define_deposits!(Deposits, register_all, ingredients => [
+    stone = DepositCreateInfo::new(ingredients.stone, DepositYield::Infinite, 0.08, 0.78),
+    coal = DepositCreateInfo::new(ingredients.coal, DepositYield::Finite(200, 800), 0.12, 0.8),
+    iron_ore = DepositCreateInfo::new(ingredients.iron_ore, DepositYield::Finite(100, 500), 0.12, 0.83),
+]);
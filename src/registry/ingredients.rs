@@ -26,4 +26,8 @@ macro_rules! define_ingredients {
 
 define_ingredients!(Ingredients, register_all, [
     stone = IngredientCreator::read(include_str!("files/ingredients/stone.xml")),
+    water = IngredientCreator::read(include_str!("files/ingredients/water.xml")),
+    lava = IngredientCreator::read(include_str!("files/ingredients/lava.xml")),
+    coal = IngredientCreator::read(include_str!("files/ingredients/coal.xml")),
+    iron_ore = IngredientCreator::read(include_str!("files/ingredients/iron_ore.xml")),
 ]);
@@ -5,8 +5,12 @@ use mvutils::Savable;
 use mvutils::save::{Loader, Savable, Saver};
 use mvutils::unsafe_utils::Unsafe;
 use parking_lot::RwLock;
+use crate::registry::biomes::Biomes;
+use crate::registry::deposits::Deposits;
 use crate::registry::ingredients::Ingredients;
 use crate::registry::multitiles::MultiTiles;
+use crate::registry::stages::GeneratorStages;
+use crate::registry::structures::Structures;
 use crate::registry::terrain::TerrainTiles;
 use crate::registry::tiles::Tiles;
 
@@ -14,6 +18,10 @@ pub mod terrain;
 pub mod tiles;
 pub mod ingredients;
 pub mod multitiles;
+pub mod deposits;
+pub mod stages;
+pub mod biomes;
+pub mod structures;
 
 pub struct Registry<T: Registerable> {
     locked: AtomicBool,
@@ -74,5 +82,9 @@ pub struct GameObjects {
     pub terrain: TerrainTiles,
     pub tiles: Tiles,
     pub ingredients: Ingredients,
-    pub multitiles: MultiTiles
+    pub multitiles: MultiTiles,
+    pub deposits: Deposits,
+    pub stages: GeneratorStages,
+    pub biomes: Biomes,
+    pub structures: Structures
 }
\ No newline at end of file
@@ -14,6 +14,15 @@ macro_rules! define_multis {
             $(pub $tile_name: usize),*
         }
 
+        impl $struct_name {
+            pub fn by_name(&self, name: &str) -> Option<usize> {
+                match name {
+                    $(stringify!($tile_name) => Some(self.$tile_name),)*
+                    _ => None
+                }
+            }
+        }
+
         pub fn $func_name($go_ident: &Tiles) -> $struct_name {
             $struct_name {
                 $(
@@ -0,0 +1,86 @@
+use mvutils::lazy;
+use crate::registry::{Registerable, Registry};
+use crate::world::generate::stages::{BiomeStage, DebugStage, DepositStage, FlatStage, HeightmapStage, IslandStage, LegacyStage, SpawnStage, StructureStage, TerrainStage};
+use crate::world::generate::{stages, ChunkGenerator, StageConfig};
+
+lazy! {
+    pub static STAGE_REGISTRY: Registry<StageType> = Registry::new();
+}
+
+pub type StageFactory = fn(&StageConfig, u32) -> Box<dyn ChunkGenerator>;
+
+/// A generator stage that can be referenced by name from a [`StageConfig`].
+#[derive(Clone)]
+pub struct StageType {
+    pub id: usize,
+    pub name: String,
+    pub default_order: i32,
+    pub factory: StageFactory,
+}
+
+pub struct StageCreateInfo {
+    pub name: String,
+    pub default_order: i32,
+    pub factory: StageFactory,
+}
+
+impl StageCreateInfo {
+    pub fn new(name: &str, default_order: i32, factory: StageFactory) -> Self {
+        Self {
+            name: name.to_string(),
+            default_order,
+            factory,
+        }
+    }
+}
+
+impl Registerable for StageType {
+    type CreateInfo = StageCreateInfo;
+
+    fn with_id(id: usize, info: Self::CreateInfo) -> Self {
+        Self {
+            id,
+            name: info.name,
+            default_order: info.default_order,
+            factory: info.factory,
+        }
+    }
+}
+
+impl Registry<StageType> {
+    pub fn find(&self, name: &str) -> Option<&StageType> {
+        (0..self.len())
+            .filter_map(|id| self.reference_object(id))
+            .find(|s| s.name == name)
+    }
+}
+
+macro_rules! define_stages {
+    ($struct_name:ident, $func_name:ident, [$($stage_name:ident = $stage_init:expr),* $(,)?]) => {
+        #[derive(Clone)]
+        pub struct $struct_name {
+            $(pub $stage_name: usize),*
+        }
+
+        pub fn $func_name() -> $struct_name {
+            $struct_name {
+                $(
+                    $stage_name: STAGE_REGISTRY.register($stage_init),
+                )*
+            }
+        }
+    };
+}
+
+define_stages!(GeneratorStages, register_all, [
+    heightmap = StageCreateInfo::new(stages::HEIGHTMAP, 100, |config, seed| Box::new(HeightmapStage::new(config, seed))),
+    island = StageCreateInfo::new(stages::ISLAND, 150, |config, seed| Box::new(IslandStage::new(config, seed))),
+    biome = StageCreateInfo::new(stages::BIOME, 200, |config, seed| Box::new(BiomeStage::new(config, seed))),
+    terrain = StageCreateInfo::new(stages::TERRAIN, 300, |_, _| Box::new(TerrainStage)),
+    deposits = StageCreateInfo::new(stages::DEPOSITS, 400, |_, seed| Box::new(DepositStage::new(seed))),
+    structures = StageCreateInfo::new(stages::STRUCTURES, 500, |_, seed| Box::new(StructureStage::new(seed))),
+    spawn = StageCreateInfo::new(stages::SPAWN, 600, |_, _| Box::new(SpawnStage)),
+    flat = StageCreateInfo::new(stages::FLAT, 300, |config, _| Box::new(FlatStage::new(config))),
+    debug = StageCreateInfo::new(stages::DEBUG, 300, |_, _| Box::new(DebugStage)),
+    legacy = StageCreateInfo::new(stages::LEGACY, 300, |_, seed| Box::new(LegacyStage::new(seed))),
+]);
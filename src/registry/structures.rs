@@ -0,0 +1,33 @@
+use mvutils::lazy;
+use crate::registry::biomes::Biomes;
+use crate::registry::multitiles::MultiTiles;
+use crate::registry::terrain::TerrainTiles;
+use crate::registry::tiles::Tiles;
+use crate::registry::Registry;
+use crate::world::structure::{StructureCreateInfo, StructureTemplate};
+
+lazy! {
+    pub static STRUCTURE_REGISTRY: Registry<StructureTemplate> = Registry::new();
+}
+
+macro_rules! define_structures {
+    ($struct_name:ident, $func_name:ident, $terrain_ident:ident, $tiles_ident:ident, $multi_ident:ident, $biome_ident:ident => [$($structure_name:ident = $structure_init:expr),* $(,)?]) => {
+        #[derive(Clone)]
+        pub struct $struct_name {
+            $(pub $structure_name: usize),*
+        }
+
+        pub fn $func_name($terrain_ident: &TerrainTiles, $tiles_ident: &Tiles, $multi_ident: &MultiTiles, $biome_ident: &Biomes) -> $struct_name {
+            $struct_name {
+                $(
+                    $structure_name: STRUCTURE_REGISTRY.register($structure_init),
+                )*
+            }
+        }
+    };
+}
+
+define_structures!(Structures, register_all, t, ti, m, b => [
+    ruins = StructureCreateInfo::read(include_str!("files/structures/ruins.xml"), t, ti, m, b),
+    outpost = StructureCreateInfo::read(include_str!("files/structures/outpost.xml"), t, ti, m, b),
+]);
@@ -1,10 +1,16 @@
 use crate::registry::Registry;
 use mvutils::lazy;
 use crate::world::tiles::implementations::Air;
+use crate::world::tiles::implementations::chunk_loader::ChunkLoaderTile;
 use crate::world::tiles::implementations::conveyor::Conveyor;
+use crate::world::tiles::implementations::drill::Drill;
 use crate::world::tiles::implementations::lamp::Lamp;
+use crate::world::tiles::implementations::pipe::Pipe;
+use crate::world::tiles::implementations::pump::Pump;
+use crate::world::tiles::implementations::tank::Tank;
 use crate::world::tiles::implementations::static_tile::StaticTile;
 use crate::world::tiles::Tile;
+use crate::fluid::volume;
 
 lazy! {
     pub static TILE_REGISTRY: Registry<Tile> = Registry::new();
@@ -17,6 +23,15 @@ macro_rules! define_tiles {
             $(pub $tile_name: usize),*
         }
 
+        impl $struct_name {
+            pub fn by_name(&self, name: &str) -> Option<usize> {
+                match name {
+                    $(stringify!($tile_name) => Some(self.$tile_name),)*
+                    _ => None
+                }
+            }
+        }
+
         pub fn $func_name() -> $struct_name {
             $struct_name {
                 $(
@@ -31,5 +46,10 @@ define_tiles!(Tiles, register_all, [
     air = Air,
     wood = StaticTile::new(100.0),
     lamp = Lamp::new(),
-    conveyor = Conveyor::new()
+    conveyor = Conveyor::new(),
+    tank = Tank::new(volume("16kL")),
+    pipe = Pipe::new(volume("100L")),
+    pump = Pump::new(volume("1kL"), volume("20L")),
+    drill = Drill::new(40, 50),
+    chunk_loader = ChunkLoaderTile::new(),
 ]);
\ No newline at end of file
@@ -1,21 +1,26 @@
 pub mod packets;
+pub mod stats;
 
 pub use crate::server::packets::{ClientBoundPacket, ServerBoundPacket};
 use crate::FactoryIsland;
+use crate::settings::GameSettings;
+use crate::world::generate::GeneratorSettings;
 use abi_stable::pmr::IsAccessible::No;
-use log::{debug, error, info, LevelFilter};
+use log::{debug, error, info, warn, LevelFilter};
 use mvengine::net::server::Server;
-use mvutils::clock::Clock;
 use parking_lot::{Condvar, Mutex, RwLock};
 use std::io::{stdout, BufRead};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::{io, thread};
 use mvutils::lazy;
 
 pub type FactoryIslandServer = Server<ServerBoundPacket, ClientBoundPacket>;
 
 pub const TPS: u16 = 20;
+/// How far the tick loop may fall behind before it skips ticks instead of catching up
+pub const MAX_CATCH_UP: Duration = Duration::from_secs(2);
 pub const INTERNAL_PORT: u16 = 4040;
 
 #[derive(Clone)]
@@ -78,46 +83,88 @@ impl ServerSync {
 }
 lazy! {
     pub(crate) static REQ_WORLD: RwLock<Option<String>> = RwLock::new(None);
+    pub(crate) static REQ_GENERATOR: RwLock<GeneratorSettings> = RwLock::new(GeneratorSettings::default());
+    pub(crate) static REQ_SETTINGS: RwLock<GameSettings> = RwLock::new(GameSettings::default());
 }
 
-pub fn startup_internal_server(logger: bool, mut sync: ServerSync, world: Option<String>) {
+pub fn startup_internal_server(logger: bool, sync: ServerSync, world: Option<String>) {
+    startup_internal_server_with_generator(logger, sync, world, GeneratorSettings::default());
+}
+
+/// Like [`startup_internal_server`], but if the world doesn't exist yet it is created with the given generator settings.
+pub fn startup_internal_server_with_generator(logger: bool, sync: ServerSync, world: Option<String>, generator: GeneratorSettings) {
+    startup_internal_server_with_settings(logger, sync, world, generator, GameSettings::default());
+}
+
+/// Like [`startup_internal_server_with_generator`], but runs the server with the given settings instead of the
+/// defaults, e.g. `GameSettings { autosave_interval: 60, ..GameSettings::default() }`.
+pub fn startup_internal_server_with_settings(logger: bool, mut sync: ServerSync, world: Option<String>, generator: GeneratorSettings, settings: GameSettings) {
     if logger {
         mvlogger::init(stdout(), LevelFilter::Debug);
     }
     
     *REQ_WORLD.write() = world;
+    *REQ_GENERATOR.write() = generator;
+    let console = settings.console;
+    *REQ_SETTINGS.write() = settings;
 
     let mut server = FactoryIslandServer::new();
     let handler = server.listen::<FactoryIsland>(INTERNAL_PORT);
     
-    let mut clock = Clock::new(TPS);
+    let tick_length = Duration::from_secs(1) / TPS as u32;
+    let mut next_tick = Instant::now();
 
     //listening for commands in console
     let handler_cloned = handler.clone();
-    let handle = thread::spawn(move || {
-        let stdin = io::stdin();
-        for line in stdin.lock().lines() {
-            match line {
-                Ok(command) => {
-                    let mut lock = handler_cloned.lock();
-                    lock.on_command(command, None);
+    let console_running = Arc::new(AtomicBool::new(true));
+    let console_flag = console_running.clone();
+    if console {
+        thread::spawn(move || {
+            let stdin = io::stdin();
+            for line in stdin.lock().lines() {
+                //reading stdin cannot be interrupted, so the thread ends with the next line after the server stopped
+                if !console_flag.load(Ordering::Acquire) {
+                    break;
+                }
+                match line {
+                    Ok(command) => {
+                        let mut lock = handler_cloned.lock();
+                        lock.on_command(command, None);
+                    }
+                    Err(e) => error!("Error reading line: {}", e),
                 }
-                Err(e) => error!("Error reading line: {}", e),
             }
-        }
-    });
+        });
+    }
 
     loop {
-        if clock.ready_and_tick() {
+        let now = Instant::now();
+        if now < next_tick {
+            thread::sleep(next_tick - now);
+        } else {
+            let behind = now - next_tick;
+            if behind > MAX_CATCH_UP {
+                let skipped = behind.as_nanos() / tick_length.as_nanos();
+                warn!("Can't keep up! Running {}ms behind, skipping {skipped} ticks", behind.as_millis());
+                handler.lock().stats.add_skipped(skipped as u64);
+                next_tick = now;
+            }
             let mut lock = handler.lock();
             lock.tick();
+            drop(lock);
+            //when behind, the next tick is due right away, so the loop catches up without sleeping
+            next_tick += tick_length;
         }
-        if sync.is_stop() {
-            let mut lock = handler.lock();
-            info!("Stopping and saving server...");
-            lock.save();
-            sync.unlock();
+        if sync.is_stop() || handler.lock().is_stopping() {
             break;
         }
     }
+
+    //the console thread is left blocked on stdin, see GameSettings::console
+    console_running.store(false, Ordering::Release);
+    handler.lock().shutdown();
+    //closes the listener, every client was disconnected already
+    drop(server);
+    sync.unlock();
+    info!("Server stopped");
 }
\ No newline at end of file
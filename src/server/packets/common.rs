@@ -18,6 +18,11 @@ pub struct PlayerData {
     pub data: ClientDataPacket
 }
 
+#[derive(Clone, Savable)]
+pub struct ServerStoppingPacket {
+    pub reason: String,
+}
+
 #[derive(Clone, Savable)]
 pub struct ServerStatePacket {
     pub players: Vec<PlayerData>,
@@ -4,10 +4,10 @@ pub mod player;
 pub mod inventory;
 
 use mvutils::Savable;
-use crate::server::packets::common::{ClientDataPacket, ServerStatePacket};
+use crate::server::packets::common::{ClientDataPacket, ServerStatePacket, ServerStoppingPacket};
 use crate::server::packets::inventory::{InventoryDataPacket, InventoryItemActionPacket, InventoryOpenPacket};
 use crate::server::packets::player::{OtherPlayerChatPacket, OtherPlayerJoinPacket, OtherPlayerLeavePacket, OtherPlayerMovePacket, PlayerChatPacket, PlayerDataPacket, PlayerMovePacket};
-use crate::server::packets::world::{ChunkDataPacket, ChunkUnloadPacket, MultiTileDestroyedPacket, MultiTilePlacedPacket, TerrainSetPacket, TileSetFromClientPacket, TileSetPacket};
+use crate::server::packets::world::{ChunkDataPacket, ChunkUnloadPacket, DepositRemovedPacket, MultiTileDestroyedPacket, MultiTilePlacedPacket, TerrainSetPacket, TileInteractPacket, TileSetFromClientPacket, TileSetPacket, TimeSyncPacket};
 
 #[derive(Savable, Clone)]
 pub enum ClientBoundPacket {
@@ -24,7 +24,10 @@ pub enum ClientBoundPacket {
     PlayerDataPacket(PlayerDataPacket),
     MultiTilePlacedPacket(MultiTilePlacedPacket),
     MultiTileDestroyedPacket(MultiTileDestroyedPacket),
-    InventoryDataPacket(InventoryDataPacket)
+    InventoryDataPacket(InventoryDataPacket),
+    DepositRemoved(DepositRemovedPacket),
+    TimeSync(TimeSyncPacket),
+    ServerStopping(ServerStoppingPacket)
 }
 
 impl ClientBoundPacket {
@@ -41,5 +44,6 @@ pub enum ServerBoundPacket {
     PlayerChat(PlayerChatPacket),
     RequestReload,
     InventoryOpenPacket(InventoryOpenPacket),
-    InventoryItemActionPacket(InventoryItemActionPacket)
+    InventoryItemActionPacket(InventoryItemActionPacket),
+    TileInteract(TileInteractPacket)
 }
\ No newline at end of file
@@ -28,6 +28,18 @@ pub struct TileSetFromClientPacket {
     pub orientation: Orientation
 }
 
+#[derive(Clone, Savable)]
+pub struct TileInteractPacket {
+    pub pos: TilePos,
+    pub action: TileAction
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Savable)]
+pub enum TileAction {
+    Rotate,
+    Use
+}
+
 #[derive(Savable, Clone)]
 pub struct ChunkDataPacket {
     pub pos: ChunkPos,
@@ -48,4 +60,18 @@ pub struct MultiTilePlacedPacket {
 pub struct MultiTileDestroyedPacket {
     pub placement_id: UUID,
     pub chunk_pos: ChunkPos,
+}
+
+#[derive(Savable, Clone)]
+pub struct DepositRemovedPacket {
+    pub pos: TilePos,
+}
+
+#[derive(Savable, Clone)]
+pub struct TimeSyncPacket {
+    /// Ticks since the world was created
+    pub time: u64,
+    /// Ticks since the last sunrise
+    pub time_of_day: u32,
+    pub day_length: u32,
 }
\ No newline at end of file
@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How many ticks the averages are taken over, 10 seconds at [`TPS`](crate::server::TPS)
+pub const SAMPLES: usize = 200;
+
+/// Time spent in the parts of a single tick.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct TickTimings {
+    pub players: Duration,
+    pub worlds: Duration,
+    /// Packets handled since the previous tick
+    pub packets: Duration,
+    pub total: Duration,
+}
+
+/// Timing of the last [`SAMPLES`] ticks.
+pub struct TickStats {
+    starts: VecDeque<Instant>,
+    timings: VecDeque<TickTimings>,
+    /// Packet handling happens between ticks, it is added to the next tick
+    pending_packets: Duration,
+    skipped: u64,
+}
+
+impl TickStats {
+    pub fn new() -> Self {
+        Self {
+            starts: VecDeque::with_capacity(SAMPLES),
+            timings: VecDeque::with_capacity(SAMPLES),
+            pending_packets: Duration::ZERO,
+            skipped: 0,
+        }
+    }
+
+    pub fn add_packet_time(&mut self, time: Duration) {
+        self.pending_packets += time;
+    }
+
+    pub fn add_skipped(&mut self, ticks: u64) {
+        self.skipped += ticks;
+    }
+
+    pub fn record(&mut self, start: Instant, mut timings: TickTimings) {
+        timings.packets = std::mem::take(&mut self.pending_packets);
+        if self.starts.len() == SAMPLES {
+            self.starts.pop_front();
+            self.timings.pop_front();
+        }
+        self.starts.push_back(start);
+        self.timings.push_back(timings);
+    }
+
+    /// Ticks per second over the last samples.
+    pub fn tps(&self) -> f64 {
+        match (self.starts.front(), self.starts.back()) {
+            (Some(first), Some(last)) if self.starts.len() > 1 => {
+                let elapsed = last.duration_since(*first).as_secs_f64();
+                if elapsed > 0.0 { (self.starts.len() - 1) as f64 / elapsed } else { 0.0 }
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn average(&self, part: impl Fn(&TickTimings) -> Duration) -> f64 {
+        if self.timings.is_empty() {
+            return 0.0;
+        }
+        let sum: Duration = self.timings.iter().map(part).sum();
+        sum.as_secs_f64() * 1000.0 / self.timings.len() as f64
+    }
+
+    /// Average milliseconds per tick.
+    pub fn mspt(&self) -> f64 {
+        self.average(|t| t.total)
+    }
+
+    pub fn max_mspt(&self) -> f64 {
+        self.timings.iter().map(|t| t.total).max().unwrap_or_default().as_secs_f64() * 1000.0
+    }
+
+    /// Average milliseconds per tick of players, worlds and packets.
+    pub fn breakdown(&self) -> (f64, f64, f64) {
+        (self.average(|t| t.players), self.average(|t| t.worlds), self.average(|t| t.packets))
+    }
+
+    /// Ticks that were skipped since the server started because it fell too far behind.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+}
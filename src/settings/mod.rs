@@ -1,3 +1,37 @@
+use crate::world::backup::RetentionPolicy;
+
+/// Passed to [`startup_internal_server_with_settings`](crate::server::startup_internal_server_with_settings).
+#[derive(Clone, Debug)]
 pub struct GameSettings {
     pub render_distance: i32,
-}
\ No newline at end of file
+    /// Chunks within this distance of a player are ticked, chunks further away are only sent to clients.
+    /// Capped at the render distance of the player
+    pub simulation_distance: i32,
+    /// Seconds between autosaves, 0 disables autosaving
+    pub autosave_interval: u32,
+    /// How many changed chunks an autosave writes per tick at most
+    pub autosave_chunks_per_tick: usize,
+    /// Seconds between backups, 0 disables scheduled backups
+    pub backup_interval: u32,
+    pub backup_retention: RetentionPolicy,
+    /// Read commands from stdin. Reading stdin cannot be interrupted, so the reading thread outlives the server and
+    /// takes the next line typed after it stopped. Embedders that read stdin themselves should turn this off
+    pub console: bool,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            render_distance: 1,
+            simulation_distance: 1,
+            autosave_interval: 300,
+            autosave_chunks_per_tick: 8,
+            backup_interval: 6 * 60 * 60,
+            backup_retention: RetentionPolicy {
+                keep: 10,
+                max_age: 7 * 24 * 60 * 60,
+            },
+            console: true,
+        }
+    }
+}
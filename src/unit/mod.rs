@@ -13,6 +13,7 @@ pub enum Unit {
     Amps(UnitPrefix),
     Kelvin(UnitPrefix),
     Moles(UnitPrefix),
+    Liters(UnitPrefix),
 
     // compound units
     // BeardFornightsPerFortnight(UnitPrefix), // m s^-1
@@ -35,8 +36,27 @@ impl Unit {
             Unit::Amps(old) => *old = p,
             Unit::Kelvin(old) => *old = p,
             Unit::Moles(old) => *old = p,
+            Unit::Liters(old) => *old = p,
         }
     }
+
+    pub fn prefix(&self) -> UnitPrefix {
+        match self {
+            Unit::None => UnitPrefix::None,
+            Unit::Meters(p) |
+            Unit::Seconds(p) |
+            Unit::Grams(p) |
+            Unit::Amps(p) |
+            Unit::Kelvin(p) |
+            Unit::Moles(p) |
+            Unit::Liters(p) => *p,
+        }
+    }
+
+    /// Converts a value in this unit to the unprefixed base unit, e.g. 5 mL -> 0.005 L
+    pub fn to_base(&self, value: f32) -> f32 {
+        value * self.prefix().factor()
+    }
 }
 
 //μ
@@ -73,4 +93,8 @@ impl UnitPrefix {
             UnitPrefix::Tera => 12,
         }
     }
+
+    pub fn factor(&self) -> f32 {
+        10f32.powi(self.power_of_ten() as i32)
+    }
 }
\ No newline at end of file
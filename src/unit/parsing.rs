@@ -80,6 +80,7 @@ fn parse_unit_suffix(s: &str) -> Option<Unit> {
         ("g",   Unit::Grams(UnitPrefix::None)),
         ("A",   Unit::Amps(UnitPrefix::None)),
         ("K",   Unit::Kelvin(UnitPrefix::None)),
+        ("L",   Unit::Liters(UnitPrefix::None)),
     ];
 
     for (unit_sym, base) in units {
@@ -0,0 +1,340 @@
+use crate::paths;
+use crate::world::storage;
+use crate::world::versioning::SaveKind;
+use crate::world::{World, WorldMeta, META_FILENAME};
+use log::{error, info, warn};
+use lz4_flex::frame::{FrameDecoder, FrameEncoder, FrameInfo};
+use hashbrown::HashSet;
+use mvutils::lazy;
+use parking_lot::Mutex;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::{fs, io};
+use std::path::{Component, Path, PathBuf};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const PATH: &str = ".factoryisland/backups";
+pub const EXTENSION: &str = "backup";
+/// Starts every backup, followed by the format version and a single lz4 frame with the entries.
+pub const MAGIC: [u8; 4] = *b"FIBK";
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Precedes every entry in the frame, the frame ends with [`END_TAG`]
+const ENTRY_TAG: u8 = 1;
+const END_TAG: u8 = 0;
+/// Longest path an entry may have, anything longer means the backup is damaged
+const MAX_PATH_LEN: u32 = 4096;
+
+lazy! {
+    /// Worlds that have a backup being written, a second one of the same world would just compete for the disk
+    static RUNNING: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+#[derive(Clone, Debug)]
+pub struct BackupInfo {
+    pub path: PathBuf,
+    /// The directory name of the world
+    pub world: String,
+    pub created: u64,
+    pub size: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RetentionPolicy {
+    /// How many backups of a world are kept, 0 keeps all of them
+    pub keep: usize,
+    /// Backups older than this many seconds are removed, 0 disables this. The newest backup is never removed.
+    pub max_age: u64,
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// The directory all backups are stored in.
+pub fn backups_dir() -> PathBuf {
+    paths::storage_root().join(PATH)
+}
+
+fn backup_path(world: &str, created: u64) -> PathBuf {
+    backups_dir().join(format!("{world}-{created}.{EXTENSION}"))
+}
+
+fn parse_name(name: &str) -> Option<(String, u64)> {
+    let (world, created) = name.strip_suffix(EXTENSION)?.strip_suffix('.')?.rsplit_once('-')?;
+    Some((world.to_string(), created.parse().ok()?))
+}
+
+/// Whether a backup of the world is being written right now. The world does not write chunks in the meantime,
+/// so that the backup sees consistent region files.
+pub fn is_running(world: &str) -> bool {
+    RUNNING.lock().contains(world)
+}
+
+/// Every file of the world below `dir`, with its path relative to `root` separated by `/`.
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<(PathBuf, String)>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+            continue;
+        }
+        //leftovers of interrupted writes and the previous copies are not part of the world
+        let extension = path.extension().and_then(|e| e.to_str());
+        if extension == Some(storage::TEMP_EXTENSION) || extension == Some(storage::BACKUP_EXTENSION) {
+            continue;
+        }
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        let relative = relative.components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.push((path, relative));
+    }
+    Ok(())
+}
+
+fn write_string(writer: &mut impl Write, string: &str) -> io::Result<()> {
+    writer.write_all(&(string.len() as u32).to_le_bytes())?;
+    writer.write_all(string.as_bytes())
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    let len = read_u32(reader)?;
+    if len > MAX_PATH_LEN {
+        return Err(io::Error::new(ErrorKind::InvalidData, "string too long"));
+    }
+    let mut bytes = vec![0; len as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Streams the files one by one into the archive, so that the world is never held in memory.
+fn write_entries(files: &[(PathBuf, String)], world: &str, created: u64, target: &Path) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(target)?);
+    file.write_all(&MAGIC)?;
+    file.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    let mut encoder = FrameEncoder::with_frame_info(FrameInfo::new().content_checksum(true), file);
+    write_string(&mut encoder, world)?;
+    encoder.write_all(&created.to_le_bytes())?;
+    for (path, relative) in files {
+        let mut source = match File::open(path) {
+            Ok(source) => source,
+            //players can leave and have their file replaced while the backup runs
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let len = source.metadata()?.len();
+        encoder.write_all(&[ENTRY_TAG])?;
+        write_string(&mut encoder, relative)?;
+        encoder.write_all(&len.to_le_bytes())?;
+        let copied = io::copy(&mut (&mut source).take(len), &mut encoder)?;
+        if copied != len {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, format!("{path:?} changed while it was read")));
+        }
+    }
+    encoder.write_all(&[END_TAG])?;
+    let file = encoder.finish().map_err(io::Error::other)?;
+    file.into_inner().map_err(|e| e.into_error())?.sync_all()
+}
+
+/// Compresses every file of the world directory `source` into a new backup. Runs on the backup thread, as this
+/// reads the whole world from disk.
+pub fn write_archive(source: &Path, world: &str, created: u64) -> Result<PathBuf, String> {
+    let mut files = Vec::new();
+    collect_files(source, source, &mut files).map_err(|e| format!("{source:?}: {e}"))?;
+    let path = backup_path(world, created);
+    fs::create_dir_all(backups_dir()).map_err(|e| e.to_string())?;
+    //not listed as a backup until it is complete
+    let temp = path.with_extension(format!("{EXTENSION}.{}", storage::TEMP_EXTENSION));
+    if let Err(e) = write_entries(&files, world, created, &temp).and_then(|_| fs::rename(&temp, &path)) {
+        let _ = fs::remove_file(&temp);
+        return Err(e.to_string());
+    }
+    Ok(path)
+}
+
+/// Saves the world and starts a thread that writes the backup, so the server keeps ticking while the files are
+/// read and compressed. Returns false if another backup of the world is still being written. Players have to be
+/// saved before this, as their lock comes before the world lock.
+pub fn start_backup(world: &mut World, retention: RetentionPolicy) -> Result<bool, String> {
+    let name = world.key().to_string();
+    if !RUNNING.lock().insert(name.clone()) {
+        return Ok(false);
+    }
+    //the last write before the backup. Until it is done saves are put off, autosaves and chunk unloading wait and
+    //unloading the world or shutting down waits for it, see World::is_backing_up
+    world.save();
+    let source = world.path.clone();
+    let thread_name = name.clone();
+    let spawned = thread::Builder::new().name("Backup".to_string()).spawn(move || {
+        let name = thread_name;
+        match write_archive(&source, &name, now()) {
+            Ok(path) => {
+                info!("Backup of world {name} written to {path:?}");
+                apply_retention(&name, retention);
+            }
+            Err(e) => error!("Failed to write the backup of world {name}: {e}"),
+        }
+        RUNNING.lock().remove(&name);
+    });
+    if let Err(e) = spawned {
+        RUNNING.lock().remove(&name);
+        return Err(e.to_string());
+    }
+    Ok(true)
+}
+
+/// All backups, or only the ones of the given world, oldest first.
+pub fn list_backups(world: Option<&str>) -> Vec<BackupInfo> {
+    let Ok(dir) = fs::read_dir(backups_dir()) else {
+        return Vec::new();
+    };
+    let world = match world.map(paths::world_key).transpose() {
+        Ok(world) => world,
+        //no backup belongs to a world with an invalid name
+        Err(_) => return Vec::new(),
+    };
+    let mut backups = dir.flatten()
+        .filter_map(|entry| {
+            let (name, created) = parse_name(entry.file_name().to_str()?)?;
+            if world.as_ref().is_some_and(|w| *w != name) {
+                return None;
+            }
+            Some(BackupInfo {
+                path: entry.path(),
+                world: name,
+                created,
+                size: entry.metadata().map_or(0, |m| m.len()),
+            })
+        })
+        .collect::<Vec<_>>();
+    backups.sort_by_key(|b| b.created);
+    backups
+}
+
+/// Removes the backups of the world the policy no longer keeps. Returns how many were removed.
+pub fn apply_retention(world: &str, retention: RetentionPolicy) -> usize {
+    let backups = list_backups(Some(world));
+    let now = now();
+    let mut removed = 0;
+    for (i, backup) in backups.iter().enumerate() {
+        let newer = backups.len() - i - 1;
+        if newer == 0 {
+            break;
+        }
+        let too_many = retention.keep > 0 && newer >= retention.keep;
+        let too_old = retention.max_age > 0 && now.saturating_sub(backup.created) > retention.max_age;
+        if too_many || too_old {
+            match fs::remove_file(&backup.path) {
+                Ok(_) => removed += 1,
+                Err(e) => warn!("Failed to remove old backup {:?}: {e}", backup.path),
+            }
+        }
+    }
+    removed
+}
+
+/// Finds a backup by its file name in the backup directory, or by a path.
+pub fn find_backup(name: &str) -> Option<PathBuf> {
+    let in_dir = backups_dir().join(name);
+    if in_dir.is_file() {
+        return Some(in_dir);
+    }
+    let with_extension = backups_dir().join(format!("{name}.{EXTENSION}"));
+    if with_extension.is_file() {
+        return Some(with_extension);
+    }
+    let path = PathBuf::from(name);
+    path.is_file().then_some(path)
+}
+
+fn is_safe(relative: &Path) -> bool {
+    relative.components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// Unpacks the backup into a new world with the given name. An existing world is never overwritten.
+pub fn restore(backup: &Path, name: &str) -> Result<PathBuf, String> {
+    let target = paths::world_dir(name)?;
+    if target.exists() {
+        return Err(format!("World {name} already exists"));
+    }
+    let mut file = BufReader::new(File::open(backup).map_err(|e| e.to_string())?);
+    let mut magic = [0; 4];
+    file.read_exact(&mut magic).map_err(|e| e.to_string())?;
+    if magic != MAGIC {
+        return Err(format!("{backup:?} is not a backup"));
+    }
+    let version = read_u32(&mut file).map_err(|e| e.to_string())?;
+    if version != FORMAT_VERSION {
+        return Err(format!("Backup format {version} is not supported"));
+    }
+    let mut decoder = FrameDecoder::new(file);
+    let world = read_string(&mut decoder).map_err(|e| e.to_string())?;
+    read_u64(&mut decoder).map_err(|e| e.to_string())?;
+
+    if let Err(e) = unpack(&mut decoder, &target, name) {
+        if let Err(e) = fs::remove_dir_all(&target) {
+            warn!("Failed to clean up the partially restored world at {target:?}: {e}");
+        }
+        return Err(e);
+    }
+    info!("Restored backup {backup:?} of world {world} as {name}");
+    Ok(target)
+}
+
+fn unpack(decoder: &mut impl Read, target: &Path, name: &str) -> Result<(), String> {
+    let damaged = |e: io::Error| format!("Backup is damaged: {e}");
+    loop {
+        let mut tag = [0; 1];
+        decoder.read_exact(&mut tag).map_err(damaged)?;
+        match tag[0] {
+            END_TAG => break,
+            ENTRY_TAG => {}
+            other => return Err(format!("Backup is damaged: unknown tag {other}")),
+        }
+        let relative = read_string(decoder).map_err(damaged)?;
+        let len = read_u64(decoder).map_err(damaged)?;
+        if !is_safe(Path::new(&relative)) {
+            return Err(format!("Backup contains an invalid path: {relative}"));
+        }
+        let path = target.join(&relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut file = File::create(&path).map_err(|e| format!("{path:?}: {e}"))?;
+        let copied = io::copy(&mut (&mut *decoder).take(len), &mut file).map_err(|e| format!("{path:?}: {e}"))?;
+        if copied != len {
+            return Err(format!("Backup is damaged: {relative} is cut off"));
+        }
+    }
+    //reading past the end verifies the checksum of the frame
+    if decoder.read(&mut [0; 1]).map_err(damaged)? != 0 {
+        return Err("Backup is damaged: data after the last entry".to_string());
+    }
+
+    //the meta still carries the name of the world the backup was taken from
+    let meta_path = target.join(META_FILENAME);
+    let mut meta = storage::read_object::<WorldMeta>(&meta_path, SaveKind::Meta)
+        .ok_or_else(|| "Backup does not contain a readable meta file".to_string())?;
+    meta.name = name.to_string();
+    if !storage::write_object(&meta_path, SaveKind::Meta, &meta) {
+        return Err("Failed to write the meta file".to_string());
+    }
+    Ok(())
+}
@@ -1,11 +1,13 @@
 use crate::multitile::MultiTilePlacement;
 use crate::registry::terrain::TERRAIN_REGISTRY;
 use crate::registry::GameObjects;
-use crate::world::generate::ChunkGenerator;
+use crate::world::generate::{GeneratePipeline, GenerationContext};
 use crate::world::tiles::pos::TilePos;
 use crate::world::tiles::{Orientation, TileType};
 use crate::world::{tiles, ChunkPos, CHUNK_SIZE};
+use crate::world::deposit::Deposit;
 use abi_stable::std_types::{RHashMap, Tuple2};
+use hashbrown::HashMap;
 use mvutils::save::custom::ignore_save;
 use mvutils::save::{Loader, Savable};
 use mvutils::Savable;
@@ -20,6 +22,16 @@ pub struct Chunk {
     pub tiles: Box<[Option<TileType>; CHUNK_TILES]>,
     pub terrain: TerrainLayer,
     pub multitiles: Vec<MultiTilePlacement>,
+    pub deposits: HashMap<u16, Deposit>,
+    /// Game time of the last tick this chunk was simulated in, 0 if it never was
+    pub last_simulated: u64,
+    /// Changed since it was last saved
+    #[unsaved]
+    pub dirty: bool,
+    /// Simulated since it was last saved. Only [`Chunk::last_simulated`] changed then, which is written when the
+    /// chunk is unloaded or the world is saved, but not by every autosave
+    #[unsaved]
+    pub simulated: bool,
 }
 
 impl Chunk {
@@ -30,6 +42,10 @@ impl Chunk {
             tiles: Box::new([0; CHUNK_TILES].map(|_| None)),
             terrain: TerrainLayer::new(),
             multitiles: vec![],
+            deposits: HashMap::new(),
+            last_simulated: 0,
+            dirty: false,
+            simulated: false,
         }
     }
 
@@ -125,19 +141,27 @@ impl Chunk {
             })
             .collect::<Vec<_>>();
 
+        let deposits = self.deposits.iter()
+            .map(|(index, deposit)| ToClientDeposit {
+                index: *index,
+                kind: deposit.kind,
+            })
+            .collect::<Vec<_>>();
+
         ToClientChunk {
             terrain,
             tiles,
             multitiles: self.multitiles.clone(),
+            deposits,
         }
     }
     
-    pub fn generate(&mut self, generator: &impl ChunkGenerator, objects: &GameObjects) {
-        generator.generate(self, objects);
+    pub fn generate(&mut self, generator: &GeneratePipeline, ctx: &mut GenerationContext, objects: &GameObjects) {
+        generator.generate(self, ctx, objects);
     }
 
-    pub fn generate_terrain(&mut self, generator: &impl ChunkGenerator, objects: &GameObjects) {
-        generator.generate_terrain(self, objects);
+    pub fn generate_terrain(&mut self, generator: &GeneratePipeline, objects: &GameObjects) -> GenerationContext {
+        generator.generate_terrain(self, objects)
     }
 }
 
@@ -216,6 +240,13 @@ pub struct ToClientChunk {
     pub terrain: Vec<ToClientObject>,
     pub tiles: Vec<Option<ToClientObject>>,
     pub multitiles: Vec<MultiTilePlacement>,
+    pub deposits: Vec<ToClientDeposit>,
+}
+
+#[derive(Clone, Savable)]
+pub struct ToClientDeposit {
+    pub index: u16,
+    pub kind: u16,
 }
 
 #[derive(Clone, Savable)]
@@ -0,0 +1,99 @@
+use crate::ingredients::IngredientKind;
+use crate::registry::Registerable;
+use mvutils::Savable;
+
+#[derive(Clone, Copy, Debug, Savable)]
+pub enum DepositYield {
+    Infinite,
+    /// Minimum and maximum amount of ingredients a single deposit tile yields.
+    /// The richer the noise at the tile, the closer it is to the maximum.
+    Finite(u32, u32),
+}
+
+/// A registered kind of deposit and how it is generated.
+#[derive(Clone)]
+pub struct DepositType {
+    pub id: usize,
+    pub ingredient: IngredientKind,
+    pub yield_amount: DepositYield,
+    /// Noise scale, smaller values mean bigger clusters
+    pub scale: f64,
+    /// Noise value from 0 to 1 above which the deposit is placed, higher values mean rarer deposits
+    pub threshold: f64,
+}
+
+impl DepositType {
+    /// Creates the deposit for a tile whose noise value is `noise_val`, if it passes the threshold.
+    pub fn create_deposit(&self, noise_val: f64) -> Option<Deposit> {
+        if noise_val <= self.threshold {
+            return None;
+        }
+        let richness = (noise_val - self.threshold) / (1.0 - self.threshold);
+        let remaining = match self.yield_amount {
+            DepositYield::Infinite => None,
+            DepositYield::Finite(min, max) => Some(min + (max.saturating_sub(min) as f64 * richness) as u32),
+        };
+        Some(Deposit {
+            kind: self.id as u16,
+            remaining,
+        })
+    }
+}
+
+pub struct DepositCreateInfo {
+    pub ingredient: IngredientKind,
+    pub yield_amount: DepositYield,
+    pub scale: f64,
+    pub threshold: f64,
+}
+
+impl DepositCreateInfo {
+    /// A finite yield with the bounds the wrong way round is swapped.
+    pub fn new(ingredient: IngredientKind, yield_amount: DepositYield, scale: f64, threshold: f64) -> Self {
+        let yield_amount = match yield_amount {
+            DepositYield::Finite(min, max) if max < min => DepositYield::Finite(max, min),
+            other => other,
+        };
+        Self {
+            ingredient,
+            yield_amount,
+            scale,
+            threshold,
+        }
+    }
+}
+
+impl Registerable for DepositType {
+    type CreateInfo = DepositCreateInfo;
+
+    fn with_id(id: usize, info: Self::CreateInfo) -> Self {
+        Self {
+            id,
+            ingredient: info.ingredient,
+            yield_amount: info.yield_amount,
+            scale: info.scale,
+            threshold: info.threshold,
+        }
+    }
+}
+
+/// A deposit placed on a single tile of a chunk.
+#[derive(Clone, Debug, Savable)]
+pub struct Deposit {
+    pub kind: u16,
+    /// `None` for infinite deposits
+    pub remaining: Option<u32>,
+}
+
+impl Deposit {
+    /// Takes one unit out of the deposit and returns whether anything is left afterwards.
+    pub fn extract(&mut self) -> bool {
+        match &mut self.remaining {
+            None => true,
+            Some(remaining) => {
+                *remaining = remaining.saturating_sub(1);
+                *remaining > 0
+            }
+        }
+    }
+}
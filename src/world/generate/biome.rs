@@ -0,0 +1,132 @@
+use crate::registry::biomes::BIOME_REGISTRY;
+use crate::registry::Registerable;
+use crate::world::generate::noise::{GeneratorNoise, NoiseConfig, NoiseKind};
+
+/// Width in climate space of the band around a biome border in which tiles of both biomes are mixed.
+pub const BLEND_WIDTH: f64 = 0.06;
+
+#[derive(Clone)]
+pub struct Biome {
+    pub id: usize,
+    pub name: String,
+    /// Position of the biome in climate space, both from 0 to 1
+    pub temperature: f64,
+    pub moisture: f64,
+    /// Terrain for every height band, sorted by the maximum height of the band
+    pub palette: Vec<(f64, usize)>,
+    /// Deposit types that may generate in this biome
+    pub deposits: Vec<usize>,
+}
+
+impl Biome {
+    pub fn terrain_for(&self, height: f64) -> usize {
+        self.palette.iter()
+            .find(|(max, _)| height <= *max)
+            .or(self.palette.last())
+            .map_or(0, |(_, terrain)| *terrain)
+    }
+
+    fn climate_distance(&self, temperature: f64, moisture: f64) -> f64 {
+        let dt = self.temperature - temperature;
+        let dm = self.moisture - moisture;
+        (dt * dt + dm * dm).sqrt()
+    }
+}
+
+pub struct BiomeCreateInfo {
+    pub name: String,
+    pub temperature: f64,
+    pub moisture: f64,
+    pub palette: Vec<(f64, usize)>,
+    pub deposits: Vec<usize>,
+}
+
+impl BiomeCreateInfo {
+    pub fn new(name: &str, temperature: f64, moisture: f64, palette: &[(f64, usize)], deposits: &[usize]) -> Self {
+        Self {
+            name: name.to_string(),
+            temperature,
+            moisture,
+            palette: palette.to_vec(),
+            deposits: deposits.to_vec(),
+        }
+    }
+}
+
+impl Registerable for Biome {
+    type CreateInfo = BiomeCreateInfo;
+
+    fn with_id(id: usize, info: Self::CreateInfo) -> Self {
+        let mut palette = info.palette;
+        palette.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            id,
+            name: info.name,
+            temperature: info.temperature,
+            moisture: info.moisture,
+            palette,
+            deposits: info.deposits,
+        }
+    }
+}
+
+/// Selects biomes from temperature and moisture noise. This is a pure function of the seed,
+/// so it can be sampled anywhere, also outside the chunk that is currently generated.
+pub struct BiomeMap {
+    seed: u32,
+    temperature: GeneratorNoise,
+    moisture: GeneratorNoise,
+}
+
+impl BiomeMap {
+    pub fn new(seed: u32, scale: f64) -> Self {
+        Self {
+            seed,
+            temperature: GeneratorNoise::from_config(&NoiseConfig::fractal(NoiseKind::Fbm, scale, 2, 100), seed),
+            moisture: GeneratorNoise::from_config(&NoiseConfig::fractal(NoiseKind::Fbm, scale, 2, 101), seed),
+        }
+    }
+
+    pub fn climate_at(&self, x: i32, z: i32) -> (f64, f64) {
+        (self.temperature.get_for_tile(x, z), self.moisture.get_for_tile(x, z))
+    }
+
+    /// The biome at a tile. Near borders the two closest biomes are dithered, with
+    /// the chance of the farther one fading out over [`BLEND_WIDTH`].
+    pub fn biome_at(&self, x: i32, z: i32) -> u16 {
+        let (temperature, moisture) = self.climate_at(x, z);
+        let mut first: Option<(usize, f64)> = None;
+        let mut second: Option<(usize, f64)> = None;
+        for id in 0..BIOME_REGISTRY.len() {
+            if let Some(biome) = BIOME_REGISTRY.reference_object(id) {
+                let d = biome.climate_distance(temperature, moisture);
+                if first.is_none_or(|(_, fd)| d < fd) {
+                    second = first;
+                    first = Some((id, d));
+                } else if second.is_none_or(|(_, sd)| d < sd) {
+                    second = Some((id, d));
+                }
+            }
+        }
+        match (first, second) {
+            (Some((b1, d1)), Some((b2, d2))) => {
+                let t = ((d2 - d1) / BLEND_WIDTH).clamp(0.0, 1.0);
+                let chance = 0.5 * (1.0 - t);
+                if tile_random(self.seed, x, z) < chance { b2 as u16 } else { b1 as u16 }
+            }
+            (Some((b1, _)), None) => b1 as u16,
+            _ => 0,
+        }
+    }
+}
+
+/// Deterministic value from 0 to 1 for a tile.
+pub fn tile_random(seed: u32, x: i32, z: i32) -> f64 {
+    let mut hash = seed ^ 0x27d4eb2d;
+    hash = hash.wrapping_add(x as u32).wrapping_mul(0x85ebca6b);
+    hash = hash.wrapping_add(z as u32).wrapping_mul(0xc2b2ae35);
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x7feb352d);
+    hash ^= hash >> 15;
+    hash as f64 / u32::MAX as f64
+}
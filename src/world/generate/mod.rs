@@ -1,17 +1,22 @@
-mod noise;
+pub mod noise;
+pub mod stages;
+pub mod biome;
 
-use crate::registry::terrain::TERRAIN_REGISTRY;
+use crate::meta::{Meta, MetaField, MetaValue};
+use crate::unit::Unit;
+use crate::registry::stages::STAGE_REGISTRY;
 use crate::registry::GameObjects;
-use crate::world::chunk::Chunk;
-use crate::world::generate::noise::GeneratorNoise;
-use crate::world::tiles::{Orientation, TileType};
-use crate::world::CHUNK_SIZE;
-use mvutils::utils::{Map, MapTo};
-use parking_lot::lock_api::RwLock;
-use crate::registry::tiles::TILE_REGISTRY;
-
-pub trait ChunkGenerator {
-    fn gen_orientation(seed: u32, x: i32, z: i32) -> u8 {
+use crate::world::generate::biome::BiomeMap;
+use crate::world::generate::noise::NoiseConfig;
+use crate::world::generate::stages::HeightmapStage;
+use crate::world::chunk::{Chunk, CHUNK_TILES};
+use crate::world::tiles::pos::TilePos;
+use log::warn;
+use std::sync::Arc;
+use mvutils::Savable;
+
+pub trait ChunkGenerator: Send + Sync {
+    fn gen_orientation(seed: u32, x: i32, z: i32) -> u8 where Self: Sized {
         let mut hash = seed;
         let x = x as u32;
         let z = z as u32;
@@ -30,49 +35,274 @@ pub trait ChunkGenerator {
         (hash & 3) as u8
     }
 
-    fn generate(&self, chunk: &mut Chunk, game_objects: &GameObjects);
-    fn generate_terrain(&self, chunk: &mut Chunk, game_objects: &GameObjects);
+    fn name(&self) -> &str;
+
+    /// Runs every time a chunk is generated or loaded, as terrain is not saved.
+    fn generate_terrain(&self, chunk: &mut Chunk, ctx: &mut GenerationContext, game_objects: &GameObjects) {}
+    /// Runs only once when a chunk is generated for the first time, after all stages generated the terrain.
+    fn generate(&self, chunk: &mut Chunk, ctx: &mut GenerationContext, game_objects: &GameObjects) {}
 }
 
-pub struct GeneratePipeline {
-    noise: GeneratorNoise
+/// Data shared between the stages while a single chunk is generated.
+pub struct GenerationContext {
+    pub seed: u32,
+    /// Height from 0 to 1 for every tile, indexed like [`Chunk::get_index`]
+    pub height: Box<[f64; CHUNK_TILES]>,
+    /// Biome id for every tile
+    pub biome: Box<[u16; CHUNK_TILES]>,
+    /// Set by the biome stage, to look up biomes outside of the chunk the same way [`GenerationContext::biome`] was filled
+    pub biome_map: Option<Arc<BiomeMap>>,
 }
 
-impl GeneratePipeline {
+impl GenerationContext {
     pub fn new(seed: u32) -> Self {
         Self {
-            noise: GeneratorNoise::new(0.1, seed),
+            seed,
+            height: Box::new([0.0; CHUNK_TILES]),
+            biome: Box::new([0; CHUNK_TILES]),
+            biome_map: None,
+        }
+    }
+
+    /// The biome at any tile, `None` if no biome stage ran.
+    pub fn biome_at(&self, chunk: &Chunk, x: i32, z: i32) -> Option<u16> {
+        let map = self.biome_map.as_ref()?;
+        let pos = TilePos::new(x, z);
+        if pos.chunk_pos == chunk.position {
+            Some(self.biome[Chunk::get_index(&pos)])
+        } else {
+            Some(map.biome_at(x, z))
+        }
+    }
+}
+
+/// Configuration of a single stage, stages are looked up by name in the [`STAGE_REGISTRY`].
+#[derive(Clone, Savable, Debug)]
+pub struct StageConfig {
+    pub stage: String,
+    pub order: i32,
+    pub params: Meta,
+    /// Overrides the default noise of stages that are driven by a single noise layer
+    pub noise: Option<NoiseConfig>,
+}
+
+impl StageConfig {
+    pub fn new(stage: &str, order: i32) -> Self {
+        Self {
+            stage: stage.to_string(),
+            order,
+            params: Meta::new(),
+            noise: None,
+        }
+    }
+
+    /// Uses the default order the stage was registered with.
+    pub fn named(stage: &str) -> Self {
+        let order = STAGE_REGISTRY.find(stage).map_or(0, |s| s.default_order);
+        Self::new(stage, order)
+    }
+
+    pub fn with(mut self, key: &str, value: MetaValue) -> Self {
+        self.params.set(key, MetaField {
+            key: key.to_string(),
+            value,
+            unit: Unit::None,
+        });
+        self
+    }
+
+    pub fn with_noise(mut self, noise: NoiseConfig) -> Self {
+        self.noise = Some(noise);
+        self
+    }
+
+    pub fn float(&self, key: &str, default: f64) -> f64 {
+        match self.params.get(key).map(|f| &f.value) {
+            Some(MetaValue::Float(f)) => *f as f64,
+            Some(MetaValue::Integer(i)) => *i as f64,
+            _ => default,
+        }
+    }
+
+    pub fn bool(&self, key: &str, default: bool) -> bool {
+        match self.params.get(key).map(|f| &f.value) {
+            Some(MetaValue::Bool(b)) => *b,
+            _ => default,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Savable, Debug, PartialEq)]
+pub enum GeneratorPreset {
+    Default,
+    /// Flat grass everywhere, nothing else besides the spawn lamp
+    Flat,
+    Island,
+    /// Nothing at all
+    Void,
+    /// Every registered tile in every orientation on a checkerboard, for testing
+    Debug,
+    /// The single noise layer worlds were generated with before there were presets
+    Legacy,
+}
+
+impl GeneratorPreset {
+    pub const ALL: [GeneratorPreset; 6] = [GeneratorPreset::Default, GeneratorPreset::Flat, GeneratorPreset::Island, GeneratorPreset::Void, GeneratorPreset::Debug, GeneratorPreset::Legacy];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GeneratorPreset::Default => "default",
+            GeneratorPreset::Flat => "flat",
+            GeneratorPreset::Island => "island",
+            GeneratorPreset::Void => "void",
+            GeneratorPreset::Debug => "debug",
+            GeneratorPreset::Legacy => "legacy",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Resolves the preset into its stages. Defaults that shape the terrain are written into the configs, so that they
+    /// are persisted with the world as well.
+    pub fn stages(&self, island: &IslandSettings) -> Vec<StageConfig> {
+        match self {
+            GeneratorPreset::Default => vec![
+                StageConfig::named(stages::HEIGHTMAP).with_noise(HeightmapStage::default_noise()),
+                StageConfig::named(stages::BIOME),
+                StageConfig::named(stages::TERRAIN),
+                StageConfig::named(stages::DEPOSITS),
+                StageConfig::named(stages::STRUCTURES),
+                StageConfig::named(stages::SPAWN),
+            ],
+            GeneratorPreset::Island => {
+                let mut stages = GeneratorPreset::Default.stages(island);
+                stages.push(island.to_stage());
+                stages
+            }
+            GeneratorPreset::Flat => vec![
+                StageConfig::named(stages::FLAT),
+                StageConfig::named(stages::SPAWN),
+            ],
+            GeneratorPreset::Void => vec![],
+            GeneratorPreset::Debug => vec![
+                StageConfig::named(stages::DEBUG),
+            ],
+            GeneratorPreset::Legacy => vec![
+                StageConfig::named(stages::LEGACY),
+                StageConfig::named(stages::SPAWN),
+            ],
+        }
+    }
+}
+
+/// Per-world generator settings, persisted in the [`WorldMeta`](crate::world::WorldMeta).
+#[derive(Clone, Savable, Debug)]
+pub struct GeneratorSettings {
+    pub preset: GeneratorPreset,
+    /// Only used by the island preset
+    pub island: IslandSettings,
+    /// The stages the preset resolved to when the world was created. Terrain is regenerated on every load, so
+    /// existing worlds keep these even if the preset changes later on.
+    pub stages: Vec<StageConfig>,
+}
+
+impl GeneratorSettings {
+    pub fn new(preset: GeneratorPreset, island: IslandSettings) -> Self {
+        Self {
+            stages: preset.stages(&island),
+            preset,
+            island,
         }
     }
+
+    pub fn preset(preset: GeneratorPreset) -> Self {
+        Self::new(preset, IslandSettings::default())
+    }
 }
 
-impl ChunkGenerator for GeneratePipeline {
-    fn generate(&self, chunk: &mut Chunk, game_objects: &GameObjects) {
-        if chunk.position == (0, 0) {
-            let tile = TILE_REGISTRY.create_object(game_objects.tiles.lamp).unwrap();
-            chunk.set_tile(0, 0, TileType::new(RwLock::new(tile)));
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        Self::preset(GeneratorPreset::Default)
+    }
+}
+
+#[derive(Clone, Savable, Debug)]
+pub struct IslandSettings {
+    /// Radius of the spawn island in tiles
+    pub radius: u32,
+    /// Generate more, smaller islands around the spawn island
+    pub archipelago: bool,
+}
+
+impl Default for IslandSettings {
+    fn default() -> Self {
+        Self {
+            radius: 256,
+            archipelago: false,
         }
     }
+}
+
+impl IslandSettings {
+    pub fn to_stage(&self) -> StageConfig {
+        StageConfig::named(stages::ISLAND)
+            .with("radius", MetaValue::Float(self.radius as f32))
+            .with("archipelago", MetaValue::Bool(self.archipelago))
+    }
+}
+
+pub struct GeneratePipeline {
+    seed: u32,
+    stages: Vec<(i32, Box<dyn ChunkGenerator>)>,
+}
+
+impl GeneratePipeline {
+    pub fn new(seed: u32, settings: &GeneratorSettings) -> Self {
+        Self::from_config(seed, &settings.stages)
+    }
 
-    fn generate_terrain(&self, chunk: &mut Chunk, game_objects: &GameObjects) {
-        for x in 0..CHUNK_SIZE {
-            for z in 0..CHUNK_SIZE {
-                let noise_x = x + chunk.position.0 * CHUNK_SIZE;
-                let noise_z = z + chunk.position.1 * CHUNK_SIZE;
-                let noise_val = self.noise.get_for_tile(noise_x, noise_z);
-                let mapped = noise_val.map_to(TERRAIN_REGISTRY.len() as u32 - 2);
-                let id = mapped + 1;
-
-                let orientation = Self::gen_orientation(chunk.seed, x, z);
-                let orientation = match orientation {
-                    0 => Orientation::North,
-                    1 => Orientation::South,
-                    2 => Orientation::West,
-                    3 => Orientation::East,
-                    _ => unreachable!()
-                };
-                chunk.set_terrain_in_generate(x, z, id as u16, orientation);
+    pub fn from_config(seed: u32, configs: &[StageConfig]) -> Self {
+        let mut this = Self {
+            seed,
+            stages: Vec::with_capacity(configs.len()),
+        };
+        for config in configs {
+            if let Some(stage) = STAGE_REGISTRY.find(&config.stage) {
+                this.add_stage(config.order, (stage.factory)(config, seed));
+            } else {
+                warn!("Unknown generator stage '{}', skipping it", config.stage);
             }
         }
+        this
+    }
+
+    /// Inserts a stage, keeping stages with the same order in insertion order.
+    pub fn add_stage(&mut self, order: i32, stage: Box<dyn ChunkGenerator>) {
+        let index = self.stages.partition_point(|(o, _)| *o <= order);
+        self.stages.insert(index, (order, stage));
+    }
+
+    pub fn stage_names(&self) -> impl Iterator<Item = &str> {
+        self.stages.iter().map(|(_, s)| s.name())
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn generate_terrain(&self, chunk: &mut Chunk, game_objects: &GameObjects) -> GenerationContext {
+        let mut ctx = GenerationContext::new(self.seed);
+        for (_, stage) in &self.stages {
+            stage.generate_terrain(chunk, &mut ctx, game_objects);
+        }
+        ctx
+    }
+
+    pub fn generate(&self, chunk: &mut Chunk, ctx: &mut GenerationContext, game_objects: &GameObjects) {
+        for (_, stage) in &self.stages {
+            stage.generate(chunk, ctx, game_objects);
+        }
     }
 }
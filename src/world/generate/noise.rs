@@ -1,21 +1,152 @@
 use mvutils::utils::Map;
-use noise::{NoiseFn, Perlin};
+use mvutils::Savable;
+use noise::{Billow, Fbm, MultiFractal, NoiseFn, Perlin, RidgedMulti};
+
+/// Offset added to all sample positions, as perlin noise is always 0 on integer lattice points.
+const LATTICE_OFFSET: f64 = 0.5;
+
+/// Derives the seed of a single noise layer from the world seed, so that layers are not correlated.
+pub fn layer_seed(seed: u32, layer: u32) -> u32 {
+    seed.wrapping_add(layer.wrapping_mul(0x9E3779B9))
+}
+
+#[derive(Clone, Copy, Savable, Debug, PartialEq)]
+pub enum NoiseKind {
+    Perlin,
+    /// Fractal brownian motion, sums up octaves of perlin noise
+    Fbm,
+    /// Sharp ridges, good for mountains and rivers
+    Ridged,
+    /// Puffy, rounded blobs
+    Billow,
+}
+
+#[derive(Clone, Savable, Debug)]
+pub struct WarpConfig {
+    pub scale: f64,
+    /// Maximum displacement in tiles
+    pub strength: f64,
+}
+
+/// Declarative description of a noise layer. The seed is derived from the world seed and `layer`.
+#[derive(Clone, Savable, Debug)]
+pub struct NoiseConfig {
+    pub kind: NoiseKind,
+    pub scale: f64,
+    pub octaves: u32,
+    pub lacunarity: f64,
+    pub persistence: f64,
+    pub layer: u32,
+    pub warp: Option<WarpConfig>,
+}
+
+impl NoiseConfig {
+    pub fn perlin(scale: f64, layer: u32) -> Self {
+        Self {
+            kind: NoiseKind::Perlin,
+            scale,
+            octaves: 1,
+            lacunarity: 2.0,
+            persistence: 0.5,
+            layer,
+            warp: None,
+        }
+    }
+
+    pub fn fractal(kind: NoiseKind, scale: f64, octaves: u32, layer: u32) -> Self {
+        Self {
+            kind,
+            octaves,
+            ..Self::perlin(scale, layer)
+        }
+    }
+
+    pub fn with_warp(mut self, scale: f64, strength: f64) -> Self {
+        self.warp = Some(WarpConfig { scale, strength });
+        self
+    }
+}
+
+enum NoiseSource {
+    Perlin(Perlin),
+    Fbm(Fbm<Perlin>),
+    Ridged(RidgedMulti<Perlin>),
+    Billow(Billow<Perlin>),
+}
+
+impl NoiseSource {
+    fn get(&self, point: [f64; 2]) -> f64 {
+        match self {
+            NoiseSource::Perlin(n) => n.get(point),
+            NoiseSource::Fbm(n) => n.get(point),
+            NoiseSource::Ridged(n) => n.get(point),
+            NoiseSource::Billow(n) => n.get(point),
+        }
+    }
+}
+
+struct DomainWarp {
+    x: Perlin,
+    z: Perlin,
+    scale: f64,
+    strength: f64,
+}
 
 pub struct GeneratorNoise {
-    perlin: Perlin,
-    scale: f64
+    source: NoiseSource,
+    scale: f64,
+    warp: Option<DomainWarp>,
 }
 
 impl GeneratorNoise {
     pub fn new(scale: f64, seed: u32) -> Self {
         Self {
-            perlin: Perlin::new(seed),
+            source: NoiseSource::Perlin(Perlin::new(seed)),
             scale,
+            warp: None,
+        }
+    }
+
+    pub fn from_config(config: &NoiseConfig, world_seed: u32) -> Self {
+        let seed = layer_seed(world_seed, config.layer);
+        let octaves = config.octaves.max(1) as usize;
+        let source = match config.kind {
+            NoiseKind::Perlin => NoiseSource::Perlin(Perlin::new(seed)),
+            NoiseKind::Fbm => NoiseSource::Fbm(Fbm::new(seed)
+                .set_octaves(octaves)
+                .set_lacunarity(config.lacunarity)
+                .set_persistence(config.persistence)),
+            NoiseKind::Ridged => NoiseSource::Ridged(RidgedMulti::new(seed)
+                .set_octaves(octaves)
+                .set_lacunarity(config.lacunarity)
+                .set_persistence(config.persistence)),
+            NoiseKind::Billow => NoiseSource::Billow(Billow::new(seed)
+                .set_octaves(octaves)
+                .set_lacunarity(config.lacunarity)
+                .set_persistence(config.persistence)),
+        };
+        let warp = config.warp.as_ref().map(|warp| DomainWarp {
+            x: Perlin::new(seed.wrapping_add(1)),
+            z: Perlin::new(seed.wrapping_add(2)),
+            scale: warp.scale,
+            strength: warp.strength,
+        });
+        Self {
+            source,
+            scale: config.scale,
+            warp,
         }
     }
 
+    /// Samples the noise at a tile, mapped to 0 to 1.
     pub fn get_for_tile(&self, x: i32, z: i32) -> f64 {
-        let val = self.perlin.get([x as f64 * self.scale + 0.5, z as f64 * self.scale + 0.5]);
-        val.map(&(-1.0..1.0), &(0.0..1.0))
+        let (mut x, mut z) = (x as f64, z as f64);
+        if let Some(warp) = &self.warp {
+            let point = [x * warp.scale + LATTICE_OFFSET, z * warp.scale + LATTICE_OFFSET];
+            x += warp.x.get(point) * warp.strength;
+            z += warp.z.get(point) * warp.strength;
+        }
+        let val = self.source.get([x * self.scale + LATTICE_OFFSET, z * self.scale + LATTICE_OFFSET]);
+        val.clamp(-1.0, 1.0).map(&(-1.0..1.0), &(0.0..1.0))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,495 @@
+use crate::registry::biomes::BIOME_REGISTRY;
+use crate::registry::deposits::DEPOSIT_REGISTRY;
+use crate::registry::multitiles::MULTI_REGISTRY;
+use crate::registry::structures::STRUCTURE_REGISTRY;
+use crate::registry::terrain::TERRAIN_REGISTRY;
+use crate::registry::tiles::TILE_REGISTRY;
+use crate::registry::GameObjects;
+use crate::world::chunk::Chunk;
+use crate::world::generate::biome::{tile_random, BiomeMap};
+use crate::world::generate::noise::{GeneratorNoise, NoiseConfig, NoiseKind};
+use crate::world::generate::{ChunkGenerator, GenerationContext, StageConfig};
+use crate::world::tiles::pos::TilePos;
+use crate::world::tiles::{Orientation, TileType};
+use crate::multitile::MultiTilePlacement;
+use crate::world::structure::StructureTemplate;
+use crate::world::{ChunkPos, CHUNK_SIZE};
+use mvutils::utils::{Map, MapTo};
+use noise::{NoiseFn, Perlin};
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+pub const HEIGHTMAP: &str = "heightmap";
+pub const ISLAND: &str = "island";
+pub const BIOME: &str = "biome";
+pub const TERRAIN: &str = "terrain";
+pub const DEPOSITS: &str = "deposits";
+pub const STRUCTURES: &str = "structures";
+pub const SPAWN: &str = "spawn";
+pub const FLAT: &str = "flat";
+pub const DEBUG: &str = "debug";
+pub const LEGACY: &str = "legacy";
+
+pub struct HeightmapStage {
+    noise: GeneratorNoise,
+}
+
+impl HeightmapStage {
+    pub fn default_noise() -> NoiseConfig {
+        NoiseConfig::fractal(NoiseKind::Fbm, 0.02, 4, 0).with_warp(0.01, 12.0)
+    }
+
+    pub fn new(config: &StageConfig, seed: u32) -> Self {
+        let noise = config.noise.clone().unwrap_or_else(Self::default_noise);
+        Self {
+            noise: GeneratorNoise::from_config(&noise, seed),
+        }
+    }
+}
+
+impl ChunkGenerator for HeightmapStage {
+    fn name(&self) -> &str {
+        HEIGHTMAP
+    }
+
+    fn generate_terrain(&self, chunk: &mut Chunk, ctx: &mut GenerationContext, _: &GameObjects) {
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let noise_x = x + chunk.position.0 * CHUNK_SIZE;
+                let noise_z = z + chunk.position.1 * CHUNK_SIZE;
+                ctx.height[Chunk::get_index_economy_edition(x, z)] = self.noise.get_for_tile(noise_x, noise_z);
+            }
+        }
+    }
+}
+
+/// Height every tile within [`SPAWN_LAND_RADIUS`] of the origin is raised to, so that spawn is never in the sea.
+pub const SPAWN_LAND_HEIGHT: f64 = 0.55;
+pub const SPAWN_LAND_RADIUS: f64 = 12.0;
+
+/// Sinks the heightmap into the sea with a radial falloff around spawn, so that it becomes an island.
+pub struct IslandStage {
+    seed: u32,
+    radius: f64,
+    archipelago: bool,
+    coast: GeneratorNoise,
+}
+
+impl IslandStage {
+    pub fn new(config: &StageConfig, seed: u32) -> Self {
+        let radius = config.float("radius", 256.0).max(SPAWN_LAND_RADIUS * 2.0);
+        Self {
+            seed,
+            radius,
+            archipelago: config.bool("archipelago", false),
+            coast: GeneratorNoise::from_config(&NoiseConfig::fractal(NoiseKind::Fbm, 4.0 / radius, 3, 200), seed),
+        }
+    }
+
+    /// Fractal noise from -1 to 1 that makes the coastline ragged.
+    fn coast_noise(&self, x: i32, z: i32) -> f64 {
+        self.coast.get_for_tile(x, z) * 2.0 - 1.0
+    }
+
+    /// Distance to the closest island center relative to that island's radius.
+    fn island_distance(&self, x: i32, z: i32) -> f64 {
+        let (fx, fz) = (x as f64, z as f64);
+        let mut closest = (fx * fx + fz * fz).sqrt() / self.radius;
+        if self.archipelago {
+            //every cell of the grid may contain one smaller island with a random center
+            let cell_size = self.radius * 3.0;
+            let cx = (fx / cell_size).floor() as i32;
+            let cz = (fz / cell_size).floor() as i32;
+            for ox in -1..=1 {
+                for oz in -1..=1 {
+                    let (gx, gz) = (cx + ox, cz + oz);
+                    if (gx, gz) == (0, 0) || tile_random(self.seed, gx, gz) < 0.35 {
+                        continue;
+                    }
+                    let center_x = (gx as f64 + tile_random(self.seed ^ 1, gx, gz)) * cell_size;
+                    let center_z = (gz as f64 + tile_random(self.seed ^ 2, gx, gz)) * cell_size;
+                    let radius = self.radius * (0.3 + 0.5 * tile_random(self.seed ^ 3, gx, gz));
+                    let dx = fx - center_x;
+                    let dz = fz - center_z;
+                    closest = closest.min((dx * dx + dz * dz).sqrt() / radius);
+                }
+            }
+        }
+        closest
+    }
+}
+
+impl ChunkGenerator for IslandStage {
+    fn name(&self) -> &str {
+        ISLAND
+    }
+
+    fn generate_terrain(&self, chunk: &mut Chunk, ctx: &mut GenerationContext, _: &GameObjects) {
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let wx = x + chunk.position.0 * CHUNK_SIZE;
+                let wz = z + chunk.position.1 * CHUNK_SIZE;
+                let index = Chunk::get_index_economy_edition(x, z);
+
+                let distance = self.island_distance(wx, wz) + self.coast_noise(wx, wz) * 0.3;
+                //0 inside the island, 1 in the open sea
+                let t = ((distance - 0.6) / 0.5).clamp(0.0, 1.0);
+                let falloff = t * t * (3.0 - 2.0 * t);
+                let mut height = ctx.height[index] * (1.0 - falloff);
+
+                let spawn_distance = ((wx * wx + wz * wz) as f64).sqrt();
+                if spawn_distance < SPAWN_LAND_RADIUS * 2.0 {
+                    let guaranteed = SPAWN_LAND_HEIGHT * (1.0 - ((spawn_distance - SPAWN_LAND_RADIUS) / SPAWN_LAND_RADIUS).clamp(0.0, 1.0));
+                    height = height.max(guaranteed);
+                }
+                ctx.height[index] = height;
+            }
+        }
+    }
+}
+
+pub struct BiomeStage {
+    map: Arc<BiomeMap>,
+}
+
+impl BiomeStage {
+    pub fn new(config: &StageConfig, seed: u32) -> Self {
+        Self {
+            map: Arc::new(BiomeMap::new(seed, config.float("scale", 0.004))),
+        }
+    }
+}
+
+impl ChunkGenerator for BiomeStage {
+    fn name(&self) -> &str {
+        BIOME
+    }
+
+    fn generate_terrain(&self, chunk: &mut Chunk, ctx: &mut GenerationContext, _: &GameObjects) {
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let noise_x = x + chunk.position.0 * CHUNK_SIZE;
+                let noise_z = z + chunk.position.1 * CHUNK_SIZE;
+                ctx.biome[Chunk::get_index_economy_edition(x, z)] = self.map.biome_at(noise_x, noise_z);
+            }
+        }
+        ctx.biome_map = Some(self.map.clone());
+    }
+}
+
+/// Turns the heightmap into terrain tiles using the palette of each tile's biome.
+pub struct TerrainStage;
+
+impl ChunkGenerator for TerrainStage {
+    fn name(&self) -> &str {
+        TERRAIN
+    }
+
+    fn generate_terrain(&self, chunk: &mut Chunk, ctx: &mut GenerationContext, game_objects: &GameObjects) {
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let index = Chunk::get_index_economy_edition(x, z);
+                let height = ctx.height[index];
+                let id = BIOME_REGISTRY.reference_object(ctx.biome[index] as usize)
+                    .map_or(game_objects.terrain.grass, |biome| biome.terrain_for(height));
+
+                let orientation = Orientation::from(Self::gen_orientation(chunk.seed, x, z));
+                chunk.set_terrain_in_generate(x, z, id as u16, orientation);
+            }
+        }
+    }
+}
+
+pub struct DepositStage {
+    noise: Vec<GeneratorNoise>,
+}
+
+impl DepositStage {
+    pub fn new(seed: u32) -> Self {
+        let noise = (0..DEPOSIT_REGISTRY.len())
+            .filter_map(|id| DEPOSIT_REGISTRY.reference_object(id))
+            .map(|deposit| GeneratorNoise::from_config(&NoiseConfig::perlin(deposit.scale, deposit.id as u32 + 1), seed))
+            .collect();
+        Self { noise }
+    }
+}
+
+impl ChunkGenerator for DepositStage {
+    fn name(&self) -> &str {
+        DEPOSITS
+    }
+
+    fn generate(&self, chunk: &mut Chunk, ctx: &mut GenerationContext, game_objects: &GameObjects) {
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let index = Chunk::get_index_economy_edition(x, z);
+                let terrain = chunk.terrain.terrain[index] as usize;
+                if terrain == game_objects.terrain.water || terrain == game_objects.terrain.void {
+                    continue;
+                }
+                let Some(biome) = BIOME_REGISTRY.reference_object(ctx.biome[index] as usize) else {
+                    continue;
+                };
+                let noise_x = x + chunk.position.0 * CHUNK_SIZE;
+                let noise_z = z + chunk.position.1 * CHUNK_SIZE;
+                //the first deposit type that passes its threshold wins
+                for (id, noise) in self.noise.iter().enumerate() {
+                    if !biome.deposits.contains(&id) {
+                        continue;
+                    }
+                    if let Some(deposit_type) = DEPOSIT_REGISTRY.reference_object(id) {
+                        if let Some(deposit) = deposit_type.create_deposit(noise.get_for_tile(noise_x, noise_z)) {
+                            chunk.deposits.insert(index as u16, deposit);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Places the registered structures. Every structure is assigned to a grid cell and its position only depends
+/// on the seed, so each chunk can work out which structures overlap it and place its own part of them.
+/// Pieces that fall into a neighbouring chunk are placed when that chunk generates. Pieces over water or the void
+/// are left out, so structures on the coast end at the shore.
+pub struct StructureStage {
+    seed: u32,
+}
+
+impl StructureStage {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed,
+        }
+    }
+
+    /// All placements of all structures that overlap the given chunk. Structures restricted to biomes need the biome
+    /// stage to run before.
+    fn placements(&self, chunk: &Chunk, ctx: &GenerationContext) -> Vec<(&StructureTemplate, (i32, i32))> {
+        let chunk_pos = chunk.position;
+        let min = (chunk_pos.0 * CHUNK_SIZE, chunk_pos.1 * CHUNK_SIZE);
+        let max = (min.0 + CHUNK_SIZE - 1, min.1 + CHUNK_SIZE - 1);
+        let mut placements = vec![];
+        for id in 0..STRUCTURE_REGISTRY.len() {
+            let Some(template) = STRUCTURE_REGISTRY.reference_object(id) else {
+                continue;
+            };
+            let spacing = template.rules.spacing.max(1);
+            let (width, depth) = template.size;
+            for cx in (min.0 - width).div_euclid(spacing)..=max.0.div_euclid(spacing) {
+                for cz in (min.1 - depth).div_euclid(spacing)..=max.1.div_euclid(spacing) {
+                    let Some(origin) = template.placement_in_cell(self.seed, (cx, cz)) else {
+                        continue;
+                    };
+                    if origin.0 > max.0 || origin.1 > max.1 || origin.0 + width <= min.0 || origin.1 + depth <= min.1 {
+                        continue;
+                    }
+                    if !template.rules.biomes.is_empty() {
+                        let allowed = ctx.biome_at(chunk, origin.0, origin.1)
+                            .is_some_and(|biome| template.rules.biomes.contains(&(biome as usize)));
+                        if !allowed {
+                            continue;
+                        }
+                    }
+                    placements.push((template, origin));
+                }
+            }
+        }
+        placements
+    }
+
+    /// Converts a position relative to a structure origin into a position inside `chunk`, if it is inside.
+    fn in_chunk(chunk: ChunkPos, origin: (i32, i32), x: i32, z: i32) -> Option<(i32, i32)> {
+        let pos = TilePos::new(origin.0 + x, origin.1 + z);
+        (pos.chunk_pos == chunk).then_some((pos.in_chunk_x as i32, pos.in_chunk_z as i32))
+    }
+
+    fn is_land(chunk: &Chunk, x: i32, z: i32, game_objects: &GameObjects) -> bool {
+        let terrain = chunk.terrain.terrain[Chunk::get_index_economy_edition(x, z)] as usize;
+        terrain != game_objects.terrain.water && terrain != game_objects.terrain.void
+    }
+}
+
+impl ChunkGenerator for StructureStage {
+    fn name(&self) -> &str {
+        STRUCTURES
+    }
+
+    fn generate_terrain(&self, chunk: &mut Chunk, ctx: &mut GenerationContext, game_objects: &GameObjects) {
+        for (template, origin) in self.placements(chunk, ctx) {
+            for (x, z, terrain) in &template.terrain {
+                if let Some((x, z)) = Self::in_chunk(chunk.position, origin, *x, *z) {
+                    if !Self::is_land(chunk, x, z, game_objects) {
+                        continue;
+                    }
+                    let orientation = Orientation::from(Self::gen_orientation(chunk.seed, x, z));
+                    chunk.set_terrain_in_generate(x, z, *terrain, orientation);
+                }
+            }
+        }
+    }
+
+    fn generate(&self, chunk: &mut Chunk, ctx: &mut GenerationContext, game_objects: &GameObjects) {
+        for (template, origin) in self.placements(chunk, ctx) {
+            for (x, z, kind, orientation) in &template.tiles {
+                if let Some((x, z)) = Self::in_chunk(chunk.position, origin, *x, *z) {
+                    if !Self::is_land(chunk, x, z, game_objects) {
+                        continue;
+                    }
+                    if let Some(mut tile) = TILE_REGISTRY.create_object(*kind as usize) {
+                        //see DebugStage, chunk loaders are only counted when placed through the world
+                        if tile.instance.forces_chunk() {
+                            continue;
+                        }
+                        if let Some(orientation) = orientation {
+                            tile.instance.set_orientation(*orientation);
+                        }
+                        chunk.set_tile(x, z, TileType::new(RwLock::new(tile)));
+                    }
+                }
+            }
+            //a multitile is owned by the chunk containing its corner, just like when it is built by a player
+            for (x, z, id) in &template.multitiles {
+                let Some((cx, cz)) = Self::in_chunk(chunk.position, origin, *x, *z) else {
+                    continue;
+                };
+                if !Self::is_land(chunk, cx, cz, game_objects) {
+                    continue;
+                }
+                if let Some(multi) = MULTI_REGISTRY.reference_object(*id) {
+                    let pos = TilePos::new(origin.0 + x, origin.1 + z);
+                    chunk.multitiles.push(MultiTilePlacement::new(*id as u16, pos, multi.size));
+                }
+            }
+        }
+    }
+}
+
+/// Places the lamp at the world origin.
+pub struct SpawnStage;
+
+impl ChunkGenerator for SpawnStage {
+    fn name(&self) -> &str {
+        SPAWN
+    }
+
+    fn generate(&self, chunk: &mut Chunk, _: &mut GenerationContext, game_objects: &GameObjects) {
+        if chunk.position == (0, 0) {
+            let tile = TILE_REGISTRY.create_object(game_objects.tiles.lamp).unwrap();
+            chunk.set_tile(0, 0, TileType::new(RwLock::new(tile)));
+        }
+    }
+}
+
+
+/// Fills the chunk with a single terrain, grass unless configured otherwise with `terrain`.
+pub struct FlatStage {
+    terrain: Option<u16>,
+}
+
+impl FlatStage {
+    pub fn new(config: &StageConfig) -> Self {
+        let terrain = config.float("terrain", -1.0);
+        Self {
+            terrain: (terrain >= 0.0 && (terrain as usize) < TERRAIN_REGISTRY.len()).then_some(terrain as u16),
+        }
+    }
+}
+
+impl ChunkGenerator for FlatStage {
+    fn name(&self) -> &str {
+        FLAT
+    }
+
+    fn generate_terrain(&self, chunk: &mut Chunk, _: &mut GenerationContext, game_objects: &GameObjects) {
+        let terrain = self.terrain.unwrap_or(game_objects.terrain.grass as u16);
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                chunk.set_terrain_in_generate(x, z, terrain, Orientation::North);
+            }
+        }
+    }
+}
+
+/// Places every registered tile in every orientation on a grass and stone checkerboard.
+/// Each tile gets its own row starting at the origin, with one tile of space in between.
+pub struct DebugStage;
+
+impl ChunkGenerator for DebugStage {
+    fn name(&self) -> &str {
+        DEBUG
+    }
+
+    fn generate_terrain(&self, chunk: &mut Chunk, _: &mut GenerationContext, game_objects: &GameObjects) {
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let pos = TilePos::from_in_chunk(chunk.position, x, z);
+                let terrain = if (pos.raw.0 + pos.raw.1).rem_euclid(2) == 0 {
+                    game_objects.terrain.grass
+                } else {
+                    game_objects.terrain.stone
+                };
+                chunk.set_terrain_in_generate(x, z, terrain as u16, Orientation::North);
+            }
+        }
+    }
+
+    fn generate(&self, chunk: &mut Chunk, _: &mut GenerationContext, _: &GameObjects) {
+        //skip air
+        for id in 1..TILE_REGISTRY.len() {
+            for (column, orientation) in Orientation::ALL.into_iter().enumerate() {
+                let pos = TilePos::new(column as i32 * 2, (id as i32 - 1) * 2);
+                if pos.chunk_pos != chunk.position {
+                    continue;
+                }
+                if let Some(mut tile) = TILE_REGISTRY.create_object(id) {
+                    //generated tiles bypass World::set_tile_at, so a chunk loader would never be counted
+                    if tile.instance.forces_chunk() {
+                        continue;
+                    }
+                    tile.instance.set_orientation(orientation);
+                    chunk.set_tile(pos.in_chunk_x as i32, pos.in_chunk_z as i32, TileType::new(RwLock::new(tile)));
+                }
+            }
+        }
+    }
+}
+
+/// The terrain of worlds created before the generator was split into stages: one layer of perlin noise spread over
+/// every terrain except the void. Terrain is not saved, so those worlds need exactly this to keep their terrain.
+pub struct LegacyStage {
+    noise: Perlin,
+}
+
+impl LegacyStage {
+    const SCALE: f64 = 0.1;
+
+    pub fn new(seed: u32) -> Self {
+        Self {
+            noise: Perlin::new(seed),
+        }
+    }
+}
+
+impl ChunkGenerator for LegacyStage {
+    fn name(&self) -> &str {
+        LEGACY
+    }
+
+    fn generate_terrain(&self, chunk: &mut Chunk, _: &mut GenerationContext, _: &GameObjects) {
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let noise_x = (x + chunk.position.0 * CHUNK_SIZE) as f64;
+                let noise_z = (z + chunk.position.1 * CHUNK_SIZE) as f64;
+                //not clamped like GeneratorNoise, as that would change the terrain at the extremes
+                let noise_val = self.noise.get([noise_x * Self::SCALE + 0.5, noise_z * Self::SCALE + 0.5])
+                    .map(&(-1.0..1.0), &(0.0..1.0));
+                let id = noise_val.map_to(TERRAIN_REGISTRY.len() as u32 - 2) + 1;
+
+                let orientation = Orientation::from(Self::gen_orientation(chunk.seed, x, z));
+                chunk.set_terrain_in_generate(x, z, id as u16, orientation);
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::registry::GameObjects;
+use crate::world::chunk::Chunk;
+use crate::world::generate::GeneratePipeline;
+use crate::world::manager::ChunkManager;
+use crate::world::ChunkPos;
+use log::{debug, error};
+use parking_lot::Mutex;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+/// Upper bound for the amount of worker threads, generation is cheap enough that more rarely helps.
+pub const MAX_WORKERS: usize = 4;
+
+/// Loads and generates chunks on a pool of background threads, so that neither packet handling nor the tick
+/// ever waits for disk IO or world generation. Finished chunks are collected with [`ChunkLoader::poll`].
+pub struct ChunkLoader {
+    jobs: Option<Sender<ChunkPos>>,
+    results: Receiver<(ChunkPos, Option<Chunk>)>,
+}
+
+impl ChunkLoader {
+    pub fn new(manager: Arc<ChunkManager>, generator: Arc<GeneratePipeline>, objects: GameObjects, seed: u32) -> Self {
+        let (job_sender, job_receiver) = channel::<ChunkPos>();
+        let (result_sender, results) = channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        let workers = thread::available_parallelism().map_or(1, |n| n.get().saturating_sub(1)).clamp(1, MAX_WORKERS);
+        for i in 0..workers {
+            let jobs = job_receiver.clone();
+            let results = result_sender.clone();
+            let manager = manager.clone();
+            let generator = generator.clone();
+            let objects = objects.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("chunk-worker-{i}"))
+                .spawn(move || loop {
+                    //the lock is only held while waiting, so the other workers can take the next job
+                    let job = jobs.lock().recv();
+                    let Ok(pos) = job else {
+                        //the world was dropped
+                        break;
+                    };
+                    let chunk = Self::load_or_generate(&manager, &generator, &objects, seed, pos);
+                    if results.send((pos, chunk)).is_err() {
+                        break;
+                    }
+                });
+            if let Err(e) = spawned {
+                error!("Failed to spawn chunk worker: {e}");
+            }
+        }
+
+        Self {
+            jobs: Some(job_sender),
+            results,
+        }
+    }
+
+    /// Loads the chunk from disk, or generates it if it was never saved. `None` if it could not be read right now.
+    pub fn load_or_generate(manager: &ChunkManager, generator: &GeneratePipeline, objects: &GameObjects, seed: u32, pos: ChunkPos) -> Option<Chunk> {
+        match manager.try_load_chunk(pos, generator, objects) {
+            Ok(Some(chunk)) => Some(chunk),
+            Ok(None) => {
+                debug!("Generating new chunk at {pos:?}");
+                let mut chunk = Chunk::new(pos, seed);
+                let mut ctx = chunk.generate_terrain(generator, objects);
+                chunk.generate(generator, &mut ctx, objects);
+                //never saved so far
+                chunk.dirty = true;
+                Some(chunk)
+            }
+            Err(e) => {
+                error!("Failed to read chunk {pos:?}: {e}, trying again later");
+                None
+            }
+        }
+    }
+
+    pub fn request(&self, pos: ChunkPos) {
+        if let Some(jobs) = &self.jobs {
+            if jobs.send(pos).is_err() {
+                error!("All chunk workers died, chunk {pos:?} will never be loaded");
+            }
+        }
+    }
+
+    /// All chunks that finished since the last call, `None` for the ones that could not be read.
+    pub fn poll(&self) -> Vec<(ChunkPos, Option<Chunk>)> {
+        self.results.try_iter().collect()
+    }
+}
+
+impl Drop for ChunkLoader {
+    fn drop(&mut self) {
+        //closing the job channel stops the workers once they finished their current chunk
+        self.jobs.take();
+    }
+}
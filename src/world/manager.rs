@@ -1,62 +1,195 @@
+use crate::registry::GameObjects;
 use crate::world::chunk::Chunk;
-use crate::world::{ChunkPos, ChunkType, World};
-use bytebuffer::ByteBuffer;
-use log::{debug, error};
-use mvengine::event::EventBus;
-use mvutils::enum_val;
-use mvutils::save::Savable;
-use std::fs::File;
-use std::io::{Read, Write};
+use crate::world::generate::GeneratePipeline;
+use crate::world::region::{ChunkData, RegionStore};
+use crate::world::storage;
+use crate::world::versioning;
+use crate::world::versioning::SaveKind;
+use crate::world::ChunkPos;
+use log::{debug, error, info, warn};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, io};
 
 use lz4_flex::{compress_prepend_size, decompress_size_prepended};
-use mvengine::utils::savers::SaveArc;
-use parking_lot::Mutex;
 
 pub const PATH: &str = ".factoryisland/worlds";
+/// Chunks that could not be loaded end up here
+pub const CORRUPT_DIR: &str = "corrupt";
 
-pub struct ChunkManager;
+/// Reads and writes chunks of a single world. Chunks are stored in region files, chunks still stored
+/// in the old one file per chunk format are moved into their region when they are loaded.
+/// It is shared with the chunk workers, so it only holds the chunk directory.
+pub struct ChunkManager {
+    path: PathBuf,
+    regions: RegionStore,
+}
 
 impl ChunkManager {
-    pub fn try_load_chunk(&self, world: &World, chunk_pos: ChunkPos) -> Option<ChunkType> {
-        let dir = world.chunk_directory();
-        let filename = format!("c{}_{}.chunk", chunk_pos.0, chunk_pos.1);
-        if let Some(mut file) = dir.read_file(&filename) {
-            let mut compressed = Vec::new();
-            if let Err(_) = file.read_to_end(&mut compressed) {
-                return None;
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            regions: RegionStore::new(path.clone()),
+            path,
+        }
+    }
+
+    fn legacy_path(&self, pos: ChunkPos) -> PathBuf {
+        self.path.join(format!("c{}_{}.chunk", pos.0, pos.1))
+    }
+
+    fn parse_legacy_name(name: &str) -> Option<ChunkPos> {
+        let (x, z) = name.strip_prefix('c')?.strip_suffix(".chunk")?.split_once('_')?;
+        Some((x.parse().ok()?, z.parse().ok()?))
+    }
+
+    /// Moves the chunk out of its legacy file into its region, returns the compressed data or `None` if there is no
+    /// legacy file.
+    fn migrate_chunk(&self, pos: ChunkPos) -> io::Result<Option<Vec<u8>>> {
+        let path = self.legacy_path(pos);
+        let compressed = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        if self.regions.write(pos, &compressed) {
+            if let Err(e) = fs::remove_file(&path) {
+                warn!("Migrated chunk {pos:?} but failed to remove {path:?}: {e}");
             }
+        }
+        Ok(Some(compressed))
+    }
 
-            if let Ok(decompressed) = decompress_size_prepended(compressed.as_slice()) {
-                let mut buffer = ByteBuffer::from(decompressed);
+    /// Converts every chunk of the world that is still stored in its own file. Returns how many chunks were converted.
+    pub fn migrate_legacy(&self) -> usize {
+        let Ok(dir) = fs::read_dir(&self.path) else {
+            return 0;
+        };
+        let mut migrated = 0;
+        for entry in dir.flatten() {
+            let name = entry.file_name();
+            if let Some(pos) = name.to_str().and_then(Self::parse_legacy_name) {
+                if self.regions.contains(pos) {
+                    //a region copy always wins, it was written after the migration
+                    warn!("Chunk {pos:?} exists in both formats, keeping the region copy");
+                    continue;
+                }
+                match self.migrate_chunk(pos) {
+                    Ok(Some(_)) => migrated += 1,
+                    Ok(None) => {}
+                    Err(e) => error!("Failed to migrate chunk {pos:?}: {e}"),
+                }
+            }
+        }
+        self.regions.flush();
+        info!("Migrated {migrated} chunks to region files in {:?}", self.path);
+        migrated
+    }
 
-                debug!("loaded chunk {chunk_pos:?}");
+    /// Keeps data that could not be loaded next to the chunks, so it can be recovered by hand. The chunk must not be
+    /// generated again if this fails, as that would overwrite the only copy.
+    fn set_aside(&self, pos: ChunkPos, data: &[u8]) -> io::Result<()> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let path = self.path.join(CORRUPT_DIR).join(format!("c{}_{}_{timestamp}.chunk", pos.0, pos.1));
+        match storage::write_atomic(&path, data) {
+            Ok(_) => {
+                error!("Chunk {pos:?} could not be loaded and will be generated again, its data was saved to {path:?}");
+                Ok(())
+            }
+            Err(e) => {
+                error!("Chunk {pos:?} could not be loaded and saving its data to {path:?} failed: {e}");
+                Err(e)
+            }
+        }
+    }
 
-                let mut chunk = Chunk::load(&mut buffer).ok()?;
-                chunk.generate_terrain(world.generator(), world.objects());
+    fn decode_chunk(compressed: &[u8]) -> Result<Chunk, String> {
+        let decompressed = decompress_size_prepended(compressed).map_err(|e| e.to_string())?;
+        versioning::load_versioned(SaveKind::Chunk, &decompressed)
+    }
+
+    /// Loads the chunk, `Ok(None)` means it has to be generated. Chunks that exist but cannot be loaded are set aside
+    /// before that instead of silently being replaced. An error means the chunk could not be read right now, it must
+    /// not be generated then, as its data might still be on disk.
+    pub fn try_load_chunk(&self, chunk_pos: ChunkPos, generator: &GeneratePipeline, objects: &GameObjects) -> io::Result<Option<Chunk>> {
+        let compressed = match self.regions.read(chunk_pos)? {
+            ChunkData::Valid(data) => data,
+            ChunkData::Missing => match self.migrate_chunk(chunk_pos)? {
+                Some(data) => data,
+                None => return Ok(None),
+            },
+            ChunkData::Corrupt(data) => {
+                error!("Chunk {chunk_pos:?} failed its checksum");
+                self.set_aside(chunk_pos, &data)?;
+                return Ok(None);
+            }
+        };
+
+        match Self::decode_chunk(&compressed) {
+            Ok(mut chunk) => {
+                debug!("loaded chunk {chunk_pos:?}");
+                chunk.generate_terrain(generator, objects);
                 chunk.terrain.apply_modifications();
-                Some(SaveArc::new(Mutex::new(chunk)))
-            } else {
-                error!("Error decompressing chunk file");
-                None
+                Ok(Some(chunk))
+            }
+            Err(e) => {
+                error!("Error decoding chunk {chunk_pos:?}: {e}");
+                self.set_aside(chunk_pos, &compressed)?;
+                Ok(None)
             }
-        } else {
-            None
         }
     }
 
-    pub fn try_save_chunk(&self, world: &World, chunk: &Chunk) {
-        let dir = world.chunk_directory();
-        let filename = format!("c{}_{}.chunk", chunk.position.0, chunk.position.1);
-        if let Some(mut file) = dir.write_file(&filename) {
-            let mut buffer = ByteBuffer::new();
-            chunk.save(&mut buffer);
-
-            let compressed = compress_prepend_size(buffer.as_bytes());
+    pub fn try_save_chunk(&self, chunk: &Chunk) -> bool {
+        let compressed = compress_prepend_size(&versioning::save_versioned(SaveKind::Chunk, chunk));
 
-            file.write_all(compressed.as_slice()).expect("Failed to write to file");
+        if self.regions.write(chunk.position, &compressed) {
             debug!("Saved chunk {:?}", chunk.position);
+            true
         } else {
-            error!("failed to create or open file: {:?} in {:?}", filename, dir);
+            false
         }
     }
-}
\ No newline at end of file
+
+    /// Rewrites every stored chunk that was saved with an older version. Returns how many chunks were upgraded.
+    pub fn upgrade_all(&self) -> usize {
+        self.migrate_legacy();
+        let mut upgraded = 0;
+        for pos in self.regions.stored_chunks() {
+            let compressed = match self.regions.read(pos) {
+                Ok(ChunkData::Valid(data)) => data,
+                Ok(ChunkData::Missing) => continue,
+                Ok(ChunkData::Corrupt(_)) | Err(_) => {
+                    //left alone, it is set aside once the chunk is loaded
+                    warn!("Skipping damaged chunk {pos:?}");
+                    continue;
+                }
+            };
+            let result = decompress_size_prepended(&compressed).map_err(|e| e.to_string()).and_then(|data| {
+                if !versioning::is_outdated(SaveKind::Chunk, &data) {
+                    return Ok(None);
+                }
+                let payload = versioning::read_versioned(SaveKind::Chunk, &data)?;
+                Ok(Some(compress_prepend_size(&versioning::write_versioned(SaveKind::Chunk, &payload))))
+            });
+            match result {
+                Ok(Some(data)) => {
+                    if self.regions.write(pos, &data) {
+                        upgraded += 1;
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => error!("Failed to upgrade chunk {pos:?}: {e}"),
+            }
+        }
+        self.regions.flush();
+        upgraded
+    }
+
+    pub fn flush(&self) {
+        self.regions.flush();
+    }
+
+    pub fn exists(&self, pos: ChunkPos) -> bool {
+        self.regions.contains(pos) || self.legacy_path(pos).exists()
+    }
+}
@@ -1,12 +1,23 @@
 pub mod chunk;
 pub mod manager;
+pub mod loader;
+pub mod region;
+pub mod storage;
+pub mod versioning;
 pub mod tiles;
 pub mod generate;
+pub mod deposit;
+pub mod structure;
+pub mod backup;
+pub mod worlds;
 
 use crate::player::PlayerType;
+use crate::player::uuid::UUID;
 use crate::world::chunk::{Chunk, ToClientObject};
-use crate::world::generate::{ChunkGenerator, GeneratePipeline};
+use crate::world::generate::{GeneratePipeline, GeneratorSettings};
+use crate::world::loader::ChunkLoader;
 use crate::world::manager::ChunkManager;
+use crate::world::versioning::SaveKind;
 use crate::world::tiles::pos::{TileDistance, TilePos};
 use mvengine::event::EventBus;
 use mvengine::rendering::RenderContext;
@@ -16,15 +27,17 @@ use mvengine::utils::savers::SaveArc;
 use mvutils::once::CreateOnce;
 use mvutils::save::{Loader, Savable, Saver};
 use parking_lot::Mutex;
-use rand::{rng, RngCore};
-use std::collections::HashMap;
-use std::{env, fs};
+use rand::RngCore;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
 use std::fmt::{Debug, Formatter};
 use std::fs::File;
 use std::io::{Read, Write};
 use std::ops::Deref;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Duration;
 use abi_stable::pmr::IsAccessible::No;
 use bytebuffer::ByteBuffer;
 use hashbrown::HashSet;
@@ -34,17 +47,22 @@ use mvengine::net::server::ClientEndpoint;
 use mvutils::bytebuffer::ByteBufferExtras;
 use mvutils::{enum_val, Savable};
 use mvutils::unsafe_utils::Unsafe;
-use crate::{broadcast_all_players, registry, FactoryIsland, PLAYERS};
+use crate::{broadcast_world, fluid, paths, registry, FactoryIsland, PLAYERS};
 use crate::multitile::MultiTilePlacement;
 use crate::registry::GameObjects;
+use crate::registry::deposits::DEPOSIT_REGISTRY;
+use crate::ingredients::IngredientKind;
+use crate::fluid::FluidTank;
+use crate::world::deposit::Deposit;
 use crate::registry::multitiles::MULTI_REGISTRY;
 use crate::registry::tiles::TILE_REGISTRY;
 use crate::server::{ClientBoundPacket, ServerBoundPacket};
 use crate::server::packets::common::{ClientDataPacket, PlayerData};
 use crate::server::packets::player::{OtherPlayerChatPacket, OtherPlayerJoinPacket, OtherPlayerMovePacket};
-use crate::server::packets::world::{MultiTileDestroyedPacket, MultiTilePlacedPacket, TerrainSetPacket, TileSetPacket};
+use crate::server::packets::world::{DepositRemovedPacket, MultiTileDestroyedPacket, MultiTilePlacedPacket, TerrainSetPacket, TileSetPacket, TimeSyncPacket};
 use crate::world::tiles::terrain::{WorldTerrain, TerrainTile};
-use crate::world::tiles::{create_tile, tile_to_client, TileType};
+use crate::world::tiles::implementations::Air;
+use crate::world::tiles::{create_tile, tile_to_client, Tile, TileInstance, TileType};
 
 pub const CHUNK_SIZE: i32 = 64;
 
@@ -58,17 +76,33 @@ pub const CHUNKS_DIR : &str = "chunks";
 pub const MULTITILES_FILENAME: &str = "multitiles.sav";
 
 pub const START_FORCE_ALLOWED: u16 = 9;
+/// 20 minutes at [`TPS`](crate::server::TPS)
+pub const DEFAULT_DAY_LENGTH: u32 = 24000;
+/// Ticks between the time syncs sent to the players of a world
+pub const TIME_SYNC_INTERVAL: u64 = 200;
+/// Ticks until a chunk that could not be read is tried again
+pub const CHUNK_RETRY_DELAY: u64 = 100;
 
 #[derive(Savable)]
 pub struct WorldMeta {
     pub name: String,
     pub seed: u32,
     pub max_forced_chunks: u16,
+    /// Chunks forced with `forceload add` and the spawn chunk
     pub forced_chunks: HashSet<ChunkPos>,
+    pub generator: GeneratorSettings,
+    /// Ticks the world has been simulated for
+    pub time: u64,
+    /// Ticks a full day and night take
+    pub day_length: u32,
+    /// Ticks since the last sunrise, the first half of the day is daylight
+    pub time_of_day: u32,
+    /// Chunks forced by chunk loaders, with the number of chunk loaders in them
+    pub loader_chunks: hashbrown::HashMap<ChunkPos, u32>,
 }
 
 impl WorldMeta {
-    pub fn new(name: &str, seed: u32) -> Self {
+    pub fn new(name: &str, seed: u32, generator: GeneratorSettings) -> Self {
         let mut forced_chunks = HashSet::new();
         forced_chunks.insert((0, 0));
         Self {
@@ -76,18 +110,50 @@ impl WorldMeta {
             seed,
             max_forced_chunks: START_FORCE_ALLOWED,
             forced_chunks,
+            generator,
+            time: 0,
+            day_length: DEFAULT_DAY_LENGTH,
+            time_of_day: 0,
+            loader_chunks: hashbrown::HashMap::new(),
         }
     }
+
+    /// Whether the chunk is forced manually or by a chunk loader.
+    pub fn is_forced(&self, chunk_pos: &ChunkPos) -> bool {
+        self.forced_chunks.contains(chunk_pos) || self.loader_chunks.contains_key(chunk_pos)
+    }
+
+    /// Every forced chunk, a chunk forced both manually and by chunk loaders is only in here once.
+    pub fn all_forced(&self) -> HashSet<ChunkPos> {
+        self.forced_chunks.iter().chain(self.loader_chunks.keys()).copied().collect()
+    }
+
+    /// How much of the forced chunk quota is used.
+    pub fn forced_count(&self) -> usize {
+        self.forced_chunks.len() + self.loader_chunks.keys().filter(|pos| !self.forced_chunks.contains(*pos)).count()
+    }
 }
 
 pub struct World {
     meta: WorldMeta,
+    /// See [`World::key`]
+    key: String,
+    path: PathBuf,
     directory: SmartDir,
     chunk_directory: SmartDir,
     players_directory: SmartDir,
     loaded_chunks: HashMap<ChunkPos, ChunkType>,
-    chunk_manager: ChunkManager,
-    generator_pipeline: GeneratePipeline,
+    /// Chunks that were requested from the [`ChunkLoader`] and are not loaded yet
+    pending_chunks: HashSet<ChunkPos>,
+    /// Chunks that could not be read, with the game time they may be requested again at
+    failed_chunks: HashMap<ChunkPos, u64>,
+    /// Chunks the running autosave still has to save
+    save_queue: VecDeque<ChunkPos>,
+    /// [`World::save`] was called while a backup was being written, the chunks are saved once it is done
+    save_after_backup: bool,
+    chunk_manager: Arc<ChunkManager>,
+    chunk_loader: ChunkLoader,
+    generator_pipeline: Arc<GeneratePipeline>,
     objects: GameObjects,
     
     arc: Weak<Mutex<World>>
@@ -95,51 +161,76 @@ pub struct World {
 
 impl World {
     pub fn get_main(game_objects: GameObjects) -> WorldType {
-        if let Some(world) = World::load("main", game_objects.clone()) {
+        World::get_or_create("main", GeneratorSettings::default(), game_objects)
+    }
+
+    /// Loads the world, or creates it with a random seed and the given generator settings if it doesn't exist yet.
+    /// The generator settings of an existing world are always taken from its meta. Panics if the name is not valid or
+    /// the world exists but cannot be loaded, rather than creating a new world over its files.
+    pub fn get_or_create(name: &str, generator: GeneratorSettings, game_objects: GameObjects) -> WorldType {
+        let dir = paths::world_dir(name).unwrap_or_else(|e| panic!("{e}"));
+        if let Some(world) = World::load(name, game_objects.clone()) {
             world
+        } else if dir.exists() {
+            panic!("World {name} exists but could not be loaded, restore it from a backup");
         } else {
             let rng_seed = rand::rng().next_u32();
-            World::new("main", rng_seed, game_objects)
+            World::new(name, rng_seed, generator, game_objects)
         }
     }
 }
 
 impl World {
     pub fn load(name: &str, game_objects: GameObjects) -> Option<WorldType> {
-        let dir_name = name.replace(' ', "_");
-        let appdata = env::var("APPDATA").expect("Failed to get APPDATA environment variable");
-        let mut full = PathBuf::from(appdata);
-        full.push(manager::PATH);
-        full.push(dir_name);
+        let key = match paths::world_key(name) {
+            Ok(key) => key,
+            Err(e) => {
+                error!("{e}");
+                return None;
+            }
+        };
+        let full = paths::worlds_dir().join(&key);
 
+        let path = full.clone();
+        let chunk_path = full.join(CHUNKS_DIR);
         let directory = SmartDir::new(full);
         if !directory.exists_yet() {
             None
         } else {
             let chunk_directory = directory.join(CHUNKS_DIR);
             let players_directory = directory.join(PLAYERS_DIR);
-            if !directory.exists_file(META_FILENAME) {
-                let new_seed = rng().next_u32();
-                warn!("Meta file is not available, recreating it with a new seed: {}", new_seed);
-                let meta = WorldMeta::new(name, new_seed);
-                directory.save_object(&meta, META_FILENAME);
+            let meta_path = path.join(META_FILENAME);
+            //the terrain is generated from the seed in the meta, so a new meta would change the terrain under every
+            //saved tile. Nothing is touched, so the world can be restored from a backup.
+            let meta = storage::read_object::<WorldMeta>(&meta_path, SaveKind::Meta);
+            if meta.is_none() {
+                error!("World {name} has no readable meta file at {meta_path:?}, restore it from a backup");
             }
-            if let Some(meta) = directory.read_object::<WorldMeta>(META_FILENAME) {
-                let seed = meta.seed;
+            if let Some(meta) = meta {
+                let generator_pipeline = Arc::new(GeneratePipeline::new(meta.seed, &meta.generator));
+                let chunk_manager = Arc::new(ChunkManager::new(chunk_path));
+                let chunk_loader = ChunkLoader::new(chunk_manager.clone(), generator_pipeline.clone(), game_objects.clone(), meta.seed);
 
                 let mut this = Self {
                     meta,
+                    key,
+                    path,
                     directory,
                     chunk_directory,
                     players_directory,
                     loaded_chunks: HashMap::new(),
-                    chunk_manager: ChunkManager,
-                    generator_pipeline: GeneratePipeline::new(seed),
+                    pending_chunks: HashSet::new(),
+                    failed_chunks: HashMap::new(),
+                    save_queue: VecDeque::new(),
+                    save_after_backup: false,
+                    chunk_manager,
+                    chunk_loader,
+                    generator_pipeline,
                     objects: game_objects,
                     arc: Weak::new(),
                 };
 
-                for chunk_pos in this.meta.forced_chunks.clone() {
+                for chunk_pos in this.meta.all_forced() {
                     let _ = this.get_chunk(chunk_pos);
                 }
 
@@ -155,63 +246,208 @@ impl World {
         }
     }
 
-    pub fn new(name: &str, seed: u32, game_objects: GameObjects) -> WorldType {
-        let dir_name = name.replace(' ', "_");
-        let appdata = env::var("APPDATA").expect("Failed to get APPDATA environment variable");
-        let mut full = PathBuf::from(appdata);
-        full.push(manager::PATH);
-        full.push(dir_name);
+    /// Panics if the name is not valid, see [`paths::world_key`].
+    pub fn new(name: &str, seed: u32, generator: GeneratorSettings, game_objects: GameObjects) -> WorldType {
+        let key = paths::world_key(name).unwrap_or_else(|e| panic!("{e}"));
+        let full = paths::worlds_dir().join(&key);
 
+        let path = full.clone();
+        let chunk_path = full.join(CHUNKS_DIR);
         let directory = SmartDir::new(full);
 
         let chunk_directory = directory.join(CHUNKS_DIR);
         let players_directory = directory.join(PLAYERS_DIR);
+        let generator_pipeline = Arc::new(GeneratePipeline::new(seed, &generator));
+        let chunk_manager = Arc::new(ChunkManager::new(chunk_path));
+        let chunk_loader = ChunkLoader::new(chunk_manager.clone(), generator_pipeline.clone(), game_objects.clone(), seed);
 
         Arc::new_cyclic(|weak| {
             Mutex::new(Self {
-                meta: WorldMeta::new(name, seed),
+                generator_pipeline,
+                meta: WorldMeta::new(name, seed, generator),
+                key,
+                path,
                 directory,
                 chunk_directory,
                 players_directory,
                 loaded_chunks: HashMap::new(),
-                chunk_manager: ChunkManager {},
-                generator_pipeline: GeneratePipeline::new(seed),
+                pending_chunks: HashSet::new(),
+                failed_chunks: HashMap::new(),
+                save_queue: VecDeque::new(),
+                save_after_backup: false,
+                chunk_manager,
+                chunk_loader,
                 objects: game_objects,
                 arc: weak.clone(),
             })
         }).into()
     }
 
+    /// Saves the meta and every loaded chunk that changed or was simulated since it was last saved. While a backup of
+    /// the world is being written the chunks are only saved once it is done, see [`World::wait_for_backup`].
     pub fn save(&mut self) {
-        self.directory.save_object(&self.meta, META_FILENAME);
+        self.save_queue.clear();
+        self.save_meta();
+        if self.is_backing_up() {
+            //writing now would change the region files while the backup reads them
+            self.save_after_backup = true;
+            return;
+        }
+        self.save_after_backup = false;
         for chunk in self.loaded_chunks.values() {
-            let c = chunk.lock();
-            self.chunk_manager.try_save_chunk(self, &*c);
+            self.save_chunk(chunk);
+        }
+        self.chunk_manager.flush();
+    }
+
+    fn save_chunk(&self, chunk: &ChunkType) -> bool {
+        let mut c = chunk.lock();
+        if !c.dirty && !c.simulated {
+            return true;
+        }
+        let saved = self.chunk_manager.try_save_chunk(&*c);
+        if saved {
+            c.dirty = false;
+            c.simulated = false;
+        }
+        saved
+    }
+
+    /// Starts an autosave: the meta is saved right away, the changed chunks are queued for [`World::autosave_step`].
+    /// Chunks that were only simulated are left for [`World::save`] and unloading, as that is every ticking chunk.
+    pub fn begin_autosave(&mut self) {
+        self.save_meta();
+        self.save_queue = self.loaded_chunks.iter()
+            .filter(|(_, chunk)| chunk.lock().dirty)
+            .map(|(pos, _)| *pos)
+            .collect();
+        debug!("Autosave started with {} changed chunks", self.save_queue.len());
+    }
+
+    /// Saves up to `max` queued chunks, so that an autosave is spread over multiple ticks. Waits while a backup of the
+    /// world is being written, and runs a save that was put off by the backup once it is done.
+    pub fn autosave_step(&mut self, max: usize) {
+        if self.is_backing_up() {
+            return;
+        }
+        if self.save_after_backup {
+            self.save();
+            return;
+        }
+        if self.save_queue.is_empty() {
+            return;
         }
+        for _ in 0..max {
+            let Some(pos) = self.save_queue.pop_front() else {
+                break;
+            };
+            //might have been unloaded, and therefore saved, in the meantime
+            if let Some(chunk) = self.loaded_chunks.get(&pos) {
+                self.save_chunk(chunk);
+            }
+        }
+        if self.save_queue.is_empty() {
+            self.chunk_manager.flush();
+            debug!("Autosave finished");
+        }
+    }
+
+    /// Moves all chunks that are still stored one file per chunk into region files.
+    pub fn migrate_chunks(&self) -> usize {
+        self.chunk_manager.migrate_legacy()
+    }
+
+    /// Rewrites every file of the world that was saved with an older version, returns how many files were upgraded.
+    /// Loaded chunks are saved first, so they are included.
+    pub fn upgrade(&mut self) -> usize {
+        self.save();
+        let mut upgraded = 0;
+        let mut upgrade = |path: &Path, kind: SaveKind| match storage::upgrade_file(path, kind) {
+            Ok(true) => upgraded += 1,
+            Ok(false) => {}
+            Err(e) => error!("Failed to upgrade {path:?}: {e}"),
+        };
+        upgrade(&self.path.join(META_FILENAME), SaveKind::Meta);
+        if let Ok(dir) = fs::read_dir(self.path.join(PLAYERS_DIR)) {
+            for entry in dir.flatten() {
+                let path = entry.path();
+                if path.extension().is_some_and(|e| e == "sav") {
+                    upgrade(&path, SaveKind::Player);
+                }
+            }
+        }
+        upgraded + self.chunk_manager.upgrade_all()
     }
 
-    pub fn get_chunk(&mut self, chunk_pos: ChunkPos) -> ChunkType {
+    /// Gets the chunk, loading or generating it right away if necessary. This blocks the caller, so it is only meant
+    /// for startup and commands. The tick and anything driven by players use [`World::get_loaded_chunk`] or
+    /// [`World::request_chunk`] and skip chunks that are not loaded. `None` if the chunk could not be read.
+    pub fn get_chunk(&mut self, chunk_pos: ChunkPos) -> Option<ChunkType> {
         if let Some(chunk) = self.loaded_chunks.get(&chunk_pos) {
-            return chunk.clone();
+            return Some(chunk.clone());
         }
 
-        let loaded = self.chunk_manager.try_load_chunk(self, chunk_pos);
-        if let Some(chunk) = loaded {
-            //chunk loaded
-            self.loaded_chunks.insert(chunk_pos, chunk.clone());
-            chunk
-        } else {
-            let chunk = Chunk::new(chunk_pos, self.meta.seed);
-            let chunk = SaveArc::new(Mutex::new(chunk));
-            //generate new chunk if loading fails
-            debug!("Generating new chunk at {chunk_pos:?}");
-            let mut chunk_lock = chunk.lock();
-            chunk_lock.generate_terrain(&self.generator_pipeline, &self.objects);
-            chunk_lock.generate(&self.generator_pipeline, &self.objects);
-            let pos = chunk_lock.position;
-            drop(chunk_lock);
-            self.loaded_chunks.insert(pos, chunk.clone());
-            chunk
+        //a worker might still be busy with it, its result is dropped in poll_chunks
+        self.pending_chunks.remove(&chunk_pos);
+        let Some(chunk) = ChunkLoader::load_or_generate(&self.chunk_manager, &self.generator_pipeline, &self.objects, self.meta.seed, chunk_pos) else {
+            self.failed_chunks.insert(chunk_pos, self.meta.time + CHUNK_RETRY_DELAY);
+            return None;
+        };
+        self.failed_chunks.remove(&chunk_pos);
+        let chunk = SaveArc::new(Mutex::new(chunk));
+        self.loaded_chunks.insert(chunk_pos, chunk.clone());
+        Some(chunk)
+    }
+
+    pub fn get_loaded_chunk(&self, chunk_pos: ChunkPos) -> Option<ChunkType> {
+        self.loaded_chunks.get(&chunk_pos).cloned()
+    }
+
+    /// Returns the chunk if it is loaded, otherwise queues it to be loaded in the background.
+    /// Once [`World::poll_chunks`] picked it up, it is available through [`World::get_loaded_chunk`]. Chunks that
+    /// could not be read are only queued again after [`CHUNK_RETRY_DELAY`].
+    pub fn request_chunk(&mut self, chunk_pos: ChunkPos) -> Option<ChunkType> {
+        if let Some(chunk) = self.loaded_chunks.get(&chunk_pos) {
+            return Some(chunk.clone());
+        }
+        if self.failed_chunks.get(&chunk_pos).is_some_and(|retry_at| self.meta.time < *retry_at) {
+            return None;
+        }
+        if self.pending_chunks.insert(chunk_pos) {
+            self.chunk_loader.request(chunk_pos);
+        }
+        None
+    }
+
+    pub fn is_pending(&self, chunk_pos: ChunkPos) -> bool {
+        self.pending_chunks.contains(&chunk_pos)
+    }
+
+    /// Moves all chunks the workers finished into the loaded chunks.
+    pub fn poll_chunks(&mut self) {
+        for (pos, chunk) in self.chunk_loader.poll() {
+            //not pending anymore means it got loaded synchronously in the meantime
+            if !self.pending_chunks.remove(&pos) || self.loaded_chunks.contains_key(&pos) {
+                continue;
+            }
+            match chunk {
+                Some(chunk) => {
+                    self.failed_chunks.remove(&pos);
+                    self.loaded_chunks.insert(pos, SaveArc::new(Mutex::new(chunk)));
+                }
+                None => {
+                    self.failed_chunks.insert(pos, self.meta.time + CHUNK_RETRY_DELAY);
+                }
+            }
+        }
+
+        //players request their chunks again every tick, but nobody else asks for forced chunks that failed
+        let missing = self.meta.forced_chunks.iter().chain(self.meta.loader_chunks.keys())
+            .filter(|pos| !self.loaded_chunks.contains_key(*pos))
+            .copied()
+            .collect::<Vec<_>>();
+        for pos in missing {
+            let _ = self.request_chunk(pos);
         }
     }
 
@@ -220,20 +456,30 @@ impl World {
     }
 
     pub fn exists_file(&self, pos: ChunkPos) -> bool {
-        let filename = format!("c{}_{}.chunk", pos.0, pos.1);
-        self.chunk_directory.exists_file(&filename)
+        self.chunk_manager.exists(pos)
     }
 
     pub fn unload_chunk(&mut self, pos: ChunkPos) {
         if let Some(chunk) = self.loaded_chunks.get(&pos) {
-            let c = chunk.lock();
-            self.chunk_manager.try_save_chunk(self, &*c);
+            if !self.save_chunk(chunk) {
+                //keep it in memory rather than losing it, the next unload or save tries again
+                warn!("Chunk {pos:?} could not be saved and stays loaded");
+                return;
+            }
         }
         self.loaded_chunks.remove(&pos);
     }
 
+    /// Unloads every chunk that is not in `keep` or forced. While a backup is being written chunks stay loaded, as
+    /// unloading them would write to the region files the backup is reading.
     pub fn check_unload(&mut self, mut keep: HashSet<ChunkPos>) {
-        keep.extend(&self.meta.forced_chunks);
+        keep.extend(self.meta.all_forced());
+        //nobody waits for these anymore
+        self.pending_chunks.retain(|pos| keep.contains(pos));
+        self.failed_chunks.retain(|pos, _| keep.contains(pos));
+        if self.is_backing_up() {
+            return;
+        }
         let mut to_unload = Vec::new();
         for (pos, _) in self.loaded_chunks.iter().filter(|(c, _)| !keep.contains(*c)) {
             to_unload.push(*pos);
@@ -243,13 +489,34 @@ impl World {
         }
     }
 
+    /// Whether a backup of the world is being written. Nothing may write to the region files meanwhile.
+    pub fn is_backing_up(&self) -> bool {
+        backup::is_running(&self.key)
+    }
+
+    /// Blocks until the backup of the world that is being written is done, so that [`World::save`] writes right away.
+    /// Only meant for unloading the world and shutting down.
+    pub fn wait_for_backup(&self) {
+        if !self.is_backing_up() {
+            return;
+        }
+        info!("Waiting for the backup of {} to finish", self.meta.name);
+        while self.is_backing_up() {
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
     pub fn set_chunk(&mut self, pos: ChunkPos, chunk: ChunkType) {
         self.loaded_chunks.insert(pos, chunk);
     }
 
-    pub fn is_multitile_at(&mut self, pos: &TilePos) -> bool {
+    /// Chunks that are not loaded count as containing one, so that nothing is built over a multitile that cannot be
+    /// checked.
+    pub fn is_multitile_at(&self, pos: &TilePos) -> bool {
         for chunk_pos in pos.multitile_chunk_maybe_positions() {
-            let chunk = self.get_chunk(chunk_pos);
+            let Some(chunk) = self.get_loaded_chunk(chunk_pos) else {
+                return true;
+            };
             let lock = chunk.lock();
             if lock.multitiles.iter().any(|mt| mt.includes(pos)) {
                 return true;
@@ -258,37 +525,68 @@ impl World {
         false
     }
 
-    pub fn get_tile_at(&mut self, pos: TilePos) -> Option<TileType> {
-        let chunk = self.get_chunk((pos.world_chunk_x, pos.world_chunk_z));
-        let mut lock = chunk.lock();
+    /// The tile at `pos`, `None` if there is none or its chunk is not loaded.
+    pub fn get_tile_at(&self, pos: TilePos) -> Option<TileType> {
+        let chunk = self.get_loaded_chunk(pos.chunk_pos)?;
+        let lock = chunk.lock();
         lock.tiles[Chunk::get_index(&pos)].clone()
     }
 
-    pub fn get_tile_id_at(&mut self, pos: TilePos) -> Option<u16> {
+    pub fn get_tile_id_at(&self, pos: TilePos) -> Option<u16> {
         let lock = self.get_tile_at(pos)?;
         let x = { let x = Some(lock.read().id as u16); x }; x
     }
 
-    pub fn get_terrain_at(&mut self, pos: TilePos) -> WorldTerrain {
-        let chunk = self.get_chunk((pos.world_chunk_x, pos.world_chunk_z));
-        let mut lock = chunk.lock();
+    /// The terrain at `pos`, `None` if its chunk is not loaded.
+    pub fn get_terrain_at(&self, pos: TilePos) -> Option<WorldTerrain> {
+        let chunk = self.get_loaded_chunk(pos.chunk_pos)?;
+        let lock = chunk.lock();
         let index = Chunk::get_index(&pos);
         let id = lock.terrain.terrain[index];
         let orientation = lock.terrain.orientation[index];
-        WorldTerrain {
+        Some(WorldTerrain {
             id,
             orientation,
+        })
+    }
+
+    pub fn get_deposit_at(&self, pos: &TilePos) -> Option<Deposit> {
+        let chunk = self.get_loaded_chunk(pos.chunk_pos)?;
+        let lock = chunk.lock();
+        lock.deposits.get(&(Chunk::get_index(pos) as u16)).cloned()
+    }
+
+    /// Takes one ingredient out of the deposit at `pos`, removing the deposit once it is depleted.
+    pub fn extract_deposit(&mut self, pos: &TilePos) -> Option<IngredientKind> {
+        let chunk = self.get_loaded_chunk(pos.chunk_pos)?;
+        let mut lock = chunk.lock();
+        let index = Chunk::get_index(pos) as u16;
+        let deposit = lock.deposits.get_mut(&index)?;
+        let ingredient = DEPOSIT_REGISTRY.reference_object(deposit.kind as usize)?.ingredient;
+        let remaining = deposit.extract();
+        lock.dirty = true;
+        if !remaining {
+            lock.deposits.remove(&index);
+            drop(lock);
+            broadcast_world(&self.key, ClientBoundPacket::DepositRemoved(DepositRemovedPacket {
+                pos: pos.clone(),
+            }));
         }
+        Some(ingredient)
     }
 
-    pub fn set_tile_at(&mut self, pos: TilePos, tile: TileType, reason: TileSetReason) {
-        let chunk = self.get_chunk(pos.chunk_pos);
+    /// Replaces the tile at `pos`. Returns false if the chunk is not loaded, nothing is changed then.
+    pub fn set_tile_at(&mut self, pos: TilePos, tile: TileType, reason: TileSetReason) -> bool {
+        let Some(chunk) = self.get_loaded_chunk(pos.chunk_pos) else {
+            return false;
+        };
         let mut lock = chunk.lock();
-        lock.tiles[Chunk::get_index(&pos)] = Some(tile.clone());
+        let before = lock.tiles[Chunk::get_index(&pos)].replace(tile.clone());
+        lock.dirty = true;
 
         let client_tile = tiles::tile_to_client(&tile);
 
-        broadcast_all_players(ClientBoundPacket::TileSet(TileSetPacket {
+        broadcast_world(&self.key, ClientBoundPacket::TileSet(TileSetPacket {
             pos: pos.clone(),
             tile: client_tile,
             reason,
@@ -299,7 +597,9 @@ impl World {
 
         let mut remove = None;
         for chunk_pos in pos.multitile_chunk_maybe_positions() {
-            let chunk = self.get_chunk(chunk_pos);
+            let Some(chunk) = self.get_loaded_chunk(chunk_pos) else {
+                continue;
+            };
             let mut lock = chunk.lock();
             for i in 0..lock.multitiles.len() {
                 if lock.multitiles[i].includes(&pos) {
@@ -309,11 +609,12 @@ impl World {
             }
             if let Some(i) = remove {
                 let multiblock = lock.multitiles.remove(i);
+                lock.dirty = true;
                 let packet = ClientBoundPacket::MultiTileDestroyedPacket(MultiTileDestroyedPacket {
                     placement_id: multiblock.uuid,
                     chunk_pos: multiblock.pos.chunk_pos,
                 });
-                broadcast_all_players(packet);
+                broadcast_world(&self.key, packet);
                 break;
             }
         }
@@ -330,36 +631,120 @@ impl World {
         }
 
         if let Some(placement) = placement {
-            let chunk = self.get_chunk(placement.pos.chunk_pos);
-            let mut lock = chunk.lock();
-            lock.multitiles.push(placement.clone());
-            drop(lock);
-            let packet = ClientBoundPacket::MultiTilePlacedPacket(MultiTilePlacedPacket {
-                placement,
-            });
-            broadcast_all_players(packet);
+            //all of its tiles were just checked, so the chunk owning it is loaded
+            if let Some(chunk) = self.get_loaded_chunk(placement.pos.chunk_pos) {
+                let mut lock = chunk.lock();
+                lock.multitiles.push(placement.clone());
+                lock.dirty = true;
+                drop(lock);
+                let packet = ClientBoundPacket::MultiTilePlacedPacket(MultiTilePlacedPacket {
+                    placement,
+                });
+                broadcast_world(&self.key, packet);
+            }
         }
 
         drop(rw);
         let client_obj = tile_to_client(&tile);
 
         //also include the player that set the tile as his client only has the ghost block for now
-        broadcast_all_players(ClientBoundPacket::TileSet(TileSetPacket {
+        broadcast_world(&self.key, ClientBoundPacket::TileSet(TileSetPacket {
             pos: pos.clone(),
             tile: client_obj.clone(),
             reason: TileSetReason::DontCare,
         }));
+
+        let was_loader = before.is_some_and(|t| t.read().instance.forces_chunk());
+        let is_loader = tile.read().instance.forces_chunk();
+        if is_loader && !was_loader {
+            self.add_chunk_loader(pos.chunk_pos);
+        } else if was_loader && !is_loader {
+            self.remove_chunk_loader(pos.chunk_pos);
+        }
+        true
+    }
+
+    fn save_meta(&self) {
+        storage::write_object(&self.path.join(META_FILENAME), SaveKind::Meta, &self.meta);
+    }
+
+    pub fn is_forced(&self, chunk_pos: ChunkPos) -> bool {
+        self.meta.is_forced(&chunk_pos)
+    }
+
+    /// Whether the chunk is forced already or there is quota left to force it.
+    pub fn can_force(&self, chunk_pos: ChunkPos) -> bool {
+        self.is_forced(chunk_pos) || self.meta.forced_count() < self.meta.max_forced_chunks as usize
+    }
+
+    /// Number of chunk loaders that keep the chunk loaded.
+    pub fn chunk_loaders_in(&self, chunk_pos: ChunkPos) -> u32 {
+        self.meta.loader_chunks.get(&chunk_pos).copied().unwrap_or(0)
+    }
+
+    /// Keeps the chunk loaded and ticking even without players nearby, until [`World::unforce_chunk`] is called.
+    /// Returns false if it was forced manually already, a chunk that only chunk loaders force doesn't take more quota.
+    pub fn force_chunk(&mut self, chunk_pos: ChunkPos) -> Result<bool, String> {
+        if self.meta.forced_chunks.contains(&chunk_pos) {
+            return Ok(false);
+        }
+        if !self.can_force(chunk_pos) {
+            return Err(format!("All {} forced chunks of this world are in use", self.meta.max_forced_chunks));
+        }
+        self.meta.forced_chunks.insert(chunk_pos);
+        self.save_meta();
+        //forced chunks are never dropped from the pending chunks, so it is loaded in one of the next ticks
+        let _ = self.request_chunk(chunk_pos);
+        Ok(true)
+    }
+
+    /// Undoes [`World::force_chunk`], the chunk unloads again once no player is near and no chunk loader is in it.
+    /// Returns false if it was not forced manually.
+    pub fn unforce_chunk(&mut self, chunk_pos: ChunkPos) -> bool {
+        if !self.meta.forced_chunks.remove(&chunk_pos) {
+            return false;
+        }
+        self.save_meta();
+        true
+    }
+
+    /// Counts a chunk loader placed in the chunk. The quota is checked when the chunk loader is placed, see
+    /// [`World::can_force`].
+    fn add_chunk_loader(&mut self, chunk_pos: ChunkPos) {
+        *self.meta.loader_chunks.entry(chunk_pos).or_insert(0) += 1;
+        self.save_meta();
+    }
+
+    /// The chunk stays forced while other chunk loaders are in it or it is forced manually as well.
+    fn remove_chunk_loader(&mut self, chunk_pos: ChunkPos) {
+        let Some(count) = self.meta.loader_chunks.get_mut(&chunk_pos) else {
+            return;
+        };
+        *count -= 1;
+        if *count == 0 {
+            self.meta.loader_chunks.remove(&chunk_pos);
+        }
+        self.save_meta();
+    }
+
+    /// Changes the forced chunk quota, chunks that are forced already stay forced.
+    pub fn set_max_forced_chunks(&mut self, max: u16) {
+        self.meta.max_forced_chunks = max;
+        self.save_meta();
     }
 
     pub fn set_terrain_at(&mut self, pos: TilePos, terrain: WorldTerrain, reason: TileSetReason) {
         if let Some(template) = registry::terrain::TERRAIN_REGISTRY.create_object(terrain.id as usize) {
-            let chunk = self.get_chunk(pos.chunk_pos);
+            let Some(chunk) = self.get_loaded_chunk(pos.chunk_pos) else {
+                return;
+            };
             let mut chunk_lock = chunk.lock();
             let index = Chunk::get_index(&pos);
             let id = terrain.id;
             let orientation = terrain.orientation;
             chunk_lock.terrain.terrain[index] = id;
             chunk_lock.terrain.orientation[index] = orientation;
+            chunk_lock.dirty = true;
 
             let client_obj = ToClientObject {
                 id,
@@ -367,7 +752,7 @@ impl World {
                 state: vec![]
             };
 
-            broadcast_all_players(ClientBoundPacket::TerrainSet(TerrainSetPacket {
+            broadcast_world(&self.key, ClientBoundPacket::TerrainSet(TerrainSetPacket {
                 pos: pos.clone(),
                 tile: client_obj.clone(),
                 reason: TileSetReason::DontCare,
@@ -375,12 +760,35 @@ impl World {
         }
     }
 
+    /// Tiles are never placed where the rule cannot be checked because a chunk is not loaded.
+    pub fn can_place(&self, tile: &Tile, at: &TilePos) -> bool {
+        let rule = tile.instance.placement_rule(&self.objects.terrain);
+        rule.allows(self, at).unwrap_or(false)
+    }
+
+    /// Runs `f` on the instance of the tile without holding the lock of the tile while the world is used. The instance
+    /// is taken out for that time, so looking up the tile through the world meanwhile finds [`Air`] instead of
+    /// deadlocking.
+    pub fn with_instance<R>(&mut self, tile: &TileType, f: impl FnOnce(&mut dyn TileInstance, &mut World) -> R) -> R {
+        let mut instance = std::mem::replace(&mut tile.write().instance, Box::new(Air));
+        let result = f(instance.as_mut(), self);
+        tile.write().instance = instance;
+        result
+    }
+
+    /// Marks the chunk as changed, so that it is written on the next save.
+    pub fn mark_dirty(&self, chunk_pos: ChunkPos) {
+        if let Some(chunk) = self.loaded_chunks.get(&chunk_pos) {
+            chunk.lock().dirty = true;
+        }
+    }
+
     pub fn sync_tilestate(&mut self, at: TilePos) {
         let tile = self.get_tile_at(at.clone());
         if let Some(tile) = tile {
             let client_obj = tile_to_client(&tile);
 
-            broadcast_all_players(ClientBoundPacket::TileSet(TileSetPacket {
+            broadcast_world(&self.key, ClientBoundPacket::TileSet(TileSetPacket {
                 pos: at.clone(),
                 tile: client_obj.clone(),
                 reason: TileSetReason::DontCare,
@@ -388,8 +796,57 @@ impl World {
         }
     }
 
-    pub fn tick(&mut self) {
-        
+    /// Ticks the tiles of the simulated and the forced chunks. Chunks that were not simulated for a while first catch up
+    /// on the time they missed.
+    pub fn tick(&mut self, simulated: &HashSet<ChunkPos>) {
+        self.meta.time += 1;
+        let time = self.meta.time;
+        self.meta.time_of_day = (self.meta.time_of_day + 1) % self.meta.day_length.max(1);
+        if time % TIME_SYNC_INTERVAL == 0 {
+            self.sync_time();
+        }
+
+        let mut tiles = Vec::new();
+        let mut missed = Vec::new();
+        for (pos, chunk) in &self.loaded_chunks {
+            if !simulated.contains(pos) && !self.meta.is_forced(pos) {
+                continue;
+            }
+            let mut lock = chunk.lock();
+            let elapsed = if lock.last_simulated == 0 { 0 } else { time.saturating_sub(lock.last_simulated + 1) };
+            lock.last_simulated = time;
+            //the simulation time is saved with the chunk, otherwise it would catch up on this time again after a reload
+            lock.simulated = true;
+            if elapsed > 0 {
+                missed.extend(lock.iter_tiles().map(|(tile, at)| (tile, at, elapsed)));
+            }
+            tiles.extend(lock.iter_tiles());
+        }
+
+        for (tile, pos, elapsed) in &missed {
+            let changed = self.with_instance(tile, |instance, world| instance.catch_up(pos, world, *elapsed));
+            if changed {
+                self.mark_dirty(pos.chunk_pos);
+                self.sync_tilestate(pos.clone());
+            }
+        }
+
+        for (tile, pos) in &tiles {
+            let (changed, filled) = self.with_instance(tile, |instance, world| {
+                let volume = instance.fluid_tank().map(FluidTank::volume);
+                let changed = instance.tick(pos, world);
+                //tanks fill up in steps too small to be synced, but they still have to be saved
+                (changed, instance.fluid_tank().map(FluidTank::volume) != volume)
+            });
+            if changed || filled {
+                self.mark_dirty(pos.chunk_pos);
+            }
+            if changed {
+                self.sync_tilestate(pos.clone());
+            }
+        }
+
+        fluid::simulate(self, &tiles);
     }
 
     pub fn check_packet(&mut self, packet: ServerBoundPacket, client: &Arc<ClientEndpoint>) -> Option<ServerBoundPacket> {
@@ -416,6 +873,12 @@ impl World {
                         let mut cancel_cond = before_id as usize != self.objects.tiles.air && packet.tile_id as usize != self.objects.tiles.air;
                         //check if the player has enough reach to place that block
                         cancel_cond |= dist > reach;
+                        //players can only build in chunks that are loaded
+                        cancel_cond |= !self.is_loaded(packet.pos.chunk_pos);
+                        //check if the tile may be placed on the terrain there
+                        cancel_cond |= !self.can_place(&tile, &packet.pos);
+                        //chunk loaders need forced chunk quota
+                        cancel_cond |= tile.instance.forces_chunk() && !self.can_force(packet.pos.chunk_pos);
 
                         if cancel_cond {
                             let before = self.get_tile_at(packet.pos.clone())
@@ -435,6 +898,32 @@ impl World {
                     info!("Received Invalid tile from client with id: {}", packet.tile_id);
                 };
             },
+            ServerBoundPacket::TileInteract(packet) => {
+                let player = players.get(&client.id()).cloned();
+                drop(players);
+                if let Some(player) = player {
+                    let player_lock = player.lock();
+                    let data = player_lock.data.clone();
+                    let reach = player_lock.reach;
+                    let dist = player_lock.position.distance(&packet.pos);
+                    drop(player_lock);
+
+                    //check if the player has enough reach to interact with that tile
+                    if dist > reach {
+                        return None;
+                    }
+
+                    if let Some(tile) = self.get_tile_at(packet.pos.clone()) {
+                        let changed = self.with_instance(&tile, |instance, world| {
+                            instance.on_interact(&data, packet.action, &packet.pos, world)
+                        });
+                        if changed {
+                            self.mark_dirty(packet.pos.chunk_pos);
+                            self.sync_tilestate(packet.pos);
+                        }
+                    }
+                }
+            },
 
             other => return Some(other),
         };
@@ -445,6 +934,77 @@ impl World {
         &self.meta.name
     }
 
+    /// Identifies the world among the loaded worlds and the players in it, see [`paths::world_key`].
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn time(&self) -> u64 {
+        self.meta.time
+    }
+
+    pub fn time_of_day(&self) -> u32 {
+        self.meta.time_of_day
+    }
+
+    pub fn day_length(&self) -> u32 {
+        self.meta.day_length
+    }
+
+    pub fn is_day(&self) -> bool {
+        self.meta.time_of_day < self.meta.day_length / 2
+    }
+
+    /// How bright the sun is, from 0 at night to 1 at noon.
+    pub fn daylight(&self) -> f32 {
+        if !self.is_day() {
+            return 0.0;
+        }
+        let progress = self.meta.time_of_day as f32 / (self.meta.day_length / 2).max(1) as f32;
+        (progress * std::f32::consts::PI).sin()
+    }
+
+    pub fn set_time_of_day(&mut self, time_of_day: u32) {
+        self.meta.time_of_day = time_of_day % self.meta.day_length.max(1);
+        self.sync_time();
+    }
+
+    pub fn set_day_length(&mut self, day_length: u32) {
+        self.meta.day_length = day_length.max(1);
+        self.meta.time_of_day %= self.meta.day_length;
+        self.sync_time();
+    }
+
+    pub fn time_packet(&self) -> ClientBoundPacket {
+        ClientBoundPacket::TimeSync(TimeSyncPacket {
+            time: self.meta.time,
+            time_of_day: self.meta.time_of_day,
+            day_length: self.meta.day_length,
+        })
+    }
+
+    /// Sends the time to every player in the world.
+    pub fn sync_time(&self) {
+        broadcast_world(&self.key, self.time_packet());
+    }
+
+    pub fn meta(&self) -> &WorldMeta {
+        &self.meta
+    }
+
+    /// Reads the meta of a world without loading it.
+    pub fn read_meta(name: &str) -> Option<WorldMeta> {
+        storage::read_object(&paths::world_dir(name).ok()?.join(META_FILENAME), SaveKind::Meta)
+    }
+
+    pub fn loaded_chunk_count(&self) -> usize {
+        self.loaded_chunks.len()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     pub fn directory(&self) -> &SmartDir {
         &self.directory
     }
@@ -457,6 +1017,15 @@ impl World {
         &self.players_directory
     }
 
+    /// Path of the save file of a player.
+    pub fn player_file(&self, player: &UUID) -> PathBuf {
+        self.path.join(PLAYERS_DIR).join(format!("{player:?}.sav"))
+    }
+
+    pub fn generator_settings(&self) -> &GeneratorSettings {
+        &self.meta.generator
+    }
+
     pub fn generator(&self) -> &GeneratePipeline {
         &self.generator_pipeline
     }
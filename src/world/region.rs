@@ -0,0 +1,426 @@
+use crate::utils::crc32;
+use crate::world::manager::CORRUPT_DIR;
+use crate::world::ChunkPos;
+use hashbrown::HashMap;
+use log::{debug, error, warn};
+use parking_lot::Mutex;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, io};
+
+/// Width and depth of a region in chunks.
+pub const REGION_SIZE: i32 = 16;
+pub const REGION_CHUNKS: usize = (REGION_SIZE * REGION_SIZE) as usize;
+pub const SECTOR_SIZE: u64 = 4096;
+pub const REGION_EXTENSION: &str = "region";
+/// Regions a world keeps open at most, the least recently used one is closed when another one has to be opened.
+pub const MAX_OPEN_REGIONS: usize = 32;
+
+/// Bytes per entry in the offset table: the current location of the chunk and the one before it.
+const ENTRY_SIZE: usize = 32;
+/// The offset table, padded to whole sectors.
+const HEADER_SECTORS: u32 = ((REGION_CHUNKS * ENTRY_SIZE) as u64).div_ceil(SECTOR_SIZE) as u32;
+
+pub type RegionPos = (i32, i32);
+
+pub fn region_of(chunk: ChunkPos) -> RegionPos {
+    (chunk.0.div_euclid(REGION_SIZE), chunk.1.div_euclid(REGION_SIZE))
+}
+
+fn local_index(chunk: ChunkPos) -> usize {
+    (chunk.0.rem_euclid(REGION_SIZE) + chunk.1.rem_euclid(REGION_SIZE) * REGION_SIZE) as usize
+}
+
+pub fn region_filename(region: RegionPos) -> String {
+    format!("r{}_{}.{REGION_EXTENSION}", region.0, region.1)
+}
+
+/// Where one copy of a chunk is stored.
+#[derive(Clone, Copy, Default, Debug)]
+struct Location {
+    /// 0 means there is no copy, as sector 0 is always part of the header
+    sector: u32,
+    sectors: u32,
+    length: u32,
+    crc: u32,
+}
+
+impl Location {
+    fn is_empty(&self) -> bool {
+        self.sector == 0
+    }
+
+    fn write_to(&self, bytes: &mut [u8]) {
+        bytes[0..4].copy_from_slice(&self.sector.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.sectors.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.length.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.crc.to_le_bytes());
+    }
+
+    fn read_from(bytes: &[u8]) -> Self {
+        let word = |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        Self {
+            sector: word(0),
+            sectors: word(4),
+            length: word(8),
+            crc: word(12),
+        }
+    }
+}
+
+/// Chunks are never overwritten in place. A save goes to free sectors and only then the entry is switched over,
+/// keeping the previous copy around until the next save, so a crash mid-write always leaves one intact copy.
+#[derive(Clone, Copy, Default, Debug)]
+struct RegionEntry {
+    current: Location,
+    previous: Location,
+}
+
+impl RegionEntry {
+    fn is_empty(&self) -> bool {
+        self.current.is_empty()
+    }
+
+    fn to_bytes(&self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        self.current.write_to(&mut bytes[0..16]);
+        self.previous.write_to(&mut bytes[16..32]);
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self {
+            current: Location::read_from(&bytes[0..16]),
+            previous: Location::read_from(&bytes[16..32]),
+        }
+    }
+}
+
+pub enum ChunkData {
+    Missing,
+    Valid(Vec<u8>),
+    /// No copy passed its checksum, contains the raw bytes of the latest copy
+    Corrupt(Vec<u8>),
+}
+
+/// A file holding up to [`REGION_CHUNKS`] chunks. It starts with an offset table, followed by the chunk data,
+/// each chunk occupying a run of whole sectors.
+pub struct RegionFile {
+    file: File,
+    entries: Box<[RegionEntry; REGION_CHUNKS]>,
+    /// Which sectors are occupied, including the header
+    used: Vec<bool>,
+}
+
+impl RegionFile {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        let mut entries = Box::new([RegionEntry::default(); REGION_CHUNKS]);
+
+        let mut len = file.metadata()?.len();
+        if len > 0 && len < HEADER_SECTORS as u64 * SECTOR_SIZE {
+            //the header got cut off, the chunks it held must not be generated again as if the region was new
+            drop(file);
+            Self::set_aside(path)?;
+            file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+            len = 0;
+        }
+        if len == 0 {
+            //new file, write an empty header
+            file.set_len(HEADER_SECTORS as u64 * SECTOR_SIZE)?;
+        } else {
+            let mut header = vec![0; REGION_CHUNKS * ENTRY_SIZE];
+            file.seek(SeekFrom::Start(0))?;
+            file.read_exact(&mut header)?;
+            for (i, entry) in entries.iter_mut().enumerate() {
+                *entry = RegionEntry::from_bytes(&header[i * ENTRY_SIZE..(i + 1) * ENTRY_SIZE]);
+            }
+        }
+
+        let total = (file.metadata()?.len().div_ceil(SECTOR_SIZE) as usize).max(HEADER_SECTORS as usize);
+        let mut used = vec![false; total];
+        used[..HEADER_SECTORS as usize].fill(true);
+        for (i, entry) in entries.iter_mut().enumerate() {
+            for location in [&mut entry.current, &mut entry.previous] {
+                if location.is_empty() {
+                    continue;
+                }
+                let end = (location.sector + location.sectors) as usize;
+                if location.sector < HEADER_SECTORS || end > total || location.length as u64 > location.sectors as u64 * SECTOR_SIZE {
+                    warn!("Region file {path:?} has an invalid entry for chunk {i}, dropping it");
+                    *location = Location::default();
+                    continue;
+                }
+                used[location.sector as usize..end].fill(true);
+            }
+            if entry.current.is_empty() && !entry.previous.is_empty() {
+                entry.current = entry.previous;
+                entry.previous = Location::default();
+            }
+        }
+
+        Ok(Self { file, entries, used })
+    }
+
+    /// Moves a damaged region file into [`CORRUPT_DIR`] next to it, so it can be recovered by hand.
+    fn set_aside(path: &Path) -> io::Result<()> {
+        let directory = path.parent().unwrap_or(Path::new(".")).join(CORRUPT_DIR);
+        fs::create_dir_all(&directory)?;
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let name = path.file_name().map_or_else(|| REGION_EXTENSION.into(), |n| n.to_string_lossy().to_string());
+        let target = directory.join(format!("{name}_{timestamp}"));
+        fs::rename(path, &target)?;
+        error!("Region file {path:?} is shorter than its header, it was moved to {target:?}");
+        Ok(())
+    }
+
+    pub fn contains(&self, chunk: ChunkPos) -> bool {
+        !self.entries[local_index(chunk)].is_empty()
+    }
+
+    /// Positions of all chunks stored in this region.
+    pub fn chunks(&self, region: RegionPos) -> Vec<ChunkPos> {
+        self.entries.iter().enumerate()
+            .filter(|(_, entry)| !entry.is_empty())
+            .map(|(i, _)| (region.0 * REGION_SIZE + i as i32 % REGION_SIZE, region.1 * REGION_SIZE + i as i32 / REGION_SIZE))
+            .collect()
+    }
+
+    fn read_location(&mut self, location: &Location) -> io::Result<Vec<u8>> {
+        let mut data = vec![0; location.length as usize];
+        self.file.seek(SeekFrom::Start(location.sector as u64 * SECTOR_SIZE))?;
+        self.file.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    /// Reads the latest copy of the chunk that passes its checksum.
+    pub fn read(&mut self, chunk: ChunkPos) -> io::Result<ChunkData> {
+        let entry = self.entries[local_index(chunk)];
+        if entry.is_empty() {
+            return Ok(ChunkData::Missing);
+        }
+        //a failed read still gives the previous copy a chance
+        let latest = match self.read_location(&entry.current) {
+            Ok(data) if crc32(&data) == entry.current.crc => return Ok(ChunkData::Valid(data)),
+            other => other,
+        };
+        if !entry.previous.is_empty() {
+            warn!("Chunk {chunk:?} is damaged or unreadable, falling back to the copy from the save before");
+            match self.read_location(&entry.previous) {
+                Ok(previous) if crc32(&previous) == entry.previous.crc => return Ok(ChunkData::Valid(previous)),
+                Ok(_) => {}
+                //the copy might still be fine, so the chunk is not given up on
+                Err(e) => return Err(e),
+            }
+        }
+        //only a copy that was read and failed its checksum is damaged, a failed read says nothing about the data
+        latest.map(ChunkData::Corrupt)
+    }
+
+    pub fn write(&mut self, chunk: ChunkPos, data: &[u8]) -> io::Result<()> {
+        let index = local_index(chunk);
+        let old = self.entries[index];
+        let needed = (data.len() as u64).div_ceil(SECTOR_SIZE).max(1) as u32;
+
+        //both existing copies stay reserved while the new one is written
+        let sector = self.allocate(needed);
+        self.file.seek(SeekFrom::Start(sector as u64 * SECTOR_SIZE))?;
+        self.file.write_all(data)?;
+        let padding = (needed as u64 * SECTOR_SIZE) as usize - data.len();
+        self.file.write_all(&vec![0; padding])?;
+        self.mark(sector, needed, true);
+        //the data has to be on disk before the header points to it
+        self.file.sync_data()?;
+
+        let entry = RegionEntry {
+            current: Location {
+                sector,
+                sectors: needed,
+                length: data.len() as u32,
+                crc: crc32(data),
+            },
+            previous: old.current,
+        };
+        self.file.seek(SeekFrom::Start((index * ENTRY_SIZE) as u64))?;
+        self.file.write_all(&entry.to_bytes())?;
+        self.entries[index] = entry;
+
+        //the copy before the previous one is not needed anymore
+        if !old.previous.is_empty() {
+            self.mark(old.previous.sector, old.previous.sectors, false);
+        }
+        Ok(())
+    }
+
+    /// First fit search for `count` free sectors, growing the file if there is no gap big enough.
+    fn allocate(&mut self, count: u32) -> u32 {
+        let count = count as usize;
+        let mut run = 0;
+        for (i, used) in self.used.iter().enumerate() {
+            if *used {
+                run = 0;
+            } else {
+                run += 1;
+                if run == count {
+                    return (i + 1 - count) as u32;
+                }
+            }
+        }
+        //extend the trailing free run, if any
+        (self.used.len() - run) as u32
+    }
+
+    fn mark(&mut self, sector: u32, count: u32, used: bool) {
+        let end = (sector + count) as usize;
+        if end > self.used.len() {
+            self.used.resize(end, false);
+        }
+        self.used[sector as usize..end].fill(used);
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.sync_all()
+    }
+}
+
+struct OpenRegion {
+    file: Arc<Mutex<RegionFile>>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct OpenRegions {
+    regions: HashMap<RegionPos, OpenRegion>,
+    /// Counts up on every access, to find the least recently used region
+    clock: u64,
+}
+
+impl OpenRegions {
+    /// Closes the least recently used region that is not in use right now, if [`MAX_OPEN_REGIONS`] are open.
+    fn make_room(&mut self) {
+        if self.regions.len() < MAX_OPEN_REGIONS {
+            return;
+        }
+        //only the map holds it, and nobody can take it out while the map is locked
+        let oldest = self.regions.iter()
+            .filter(|(_, region)| Arc::strong_count(&region.file) == 1)
+            .min_by_key(|(_, region)| region.last_used)
+            .map(|(pos, _)| *pos);
+        if let Some(pos) = oldest {
+            let region = self.regions.remove(&pos).unwrap();
+            if let Err(e) = region.file.lock().flush() {
+                error!("Failed to flush region {pos:?}: {e}");
+            }
+            debug!("Closed region {pos:?}");
+        }
+    }
+}
+
+/// All region files of a world's chunk directory. Regions are opened on first use and closed again once
+/// [`MAX_OPEN_REGIONS`] are open, every region has its own lock so chunk workers only block each other within the
+/// same region.
+pub struct RegionStore {
+    directory: PathBuf,
+    open: Mutex<OpenRegions>,
+}
+
+impl RegionStore {
+    pub fn new(directory: PathBuf) -> Self {
+        Self {
+            directory,
+            open: Mutex::new(OpenRegions::default()),
+        }
+    }
+
+    fn region(&self, region: RegionPos, create: bool) -> Option<Arc<Mutex<RegionFile>>> {
+        let mut open = self.open.lock();
+        open.clock += 1;
+        let now = open.clock;
+        if let Some(file) = open.regions.get_mut(&region) {
+            file.last_used = now;
+            return Some(file.file.clone());
+        }
+        let path = self.directory.join(region_filename(region));
+        if !create && !path.exists() {
+            return None;
+        }
+        if create {
+            if let Err(e) = fs::create_dir_all(&self.directory) {
+                error!("Failed to create chunk directory {:?}: {e}", self.directory);
+                return None;
+            }
+        }
+        match RegionFile::open(&path) {
+            Ok(file) => {
+                debug!("Opened region {region:?}");
+                open.make_room();
+                let file = Arc::new(Mutex::new(file));
+                open.regions.insert(region, OpenRegion {
+                    file: file.clone(),
+                    last_used: now,
+                });
+                Some(file)
+            }
+            Err(e) => {
+                error!("Failed to open region file {path:?}: {e}");
+                None
+            }
+        }
+    }
+
+    pub fn contains(&self, chunk: ChunkPos) -> bool {
+        self.region(region_of(chunk), false).is_some_and(|r| r.lock().contains(chunk))
+    }
+
+    fn parse_region_name(name: &str) -> Option<RegionPos> {
+        let (x, z) = name.strip_prefix('r')?.strip_suffix(&format!(".{REGION_EXTENSION}"))?.split_once('_')?;
+        Some((x.parse().ok()?, z.parse().ok()?))
+    }
+
+    /// Positions of all chunks stored in any region.
+    pub fn stored_chunks(&self) -> Vec<ChunkPos> {
+        let Ok(dir) = fs::read_dir(&self.directory) else {
+            return vec![];
+        };
+        dir.flatten()
+            .filter_map(|entry| entry.file_name().to_str().and_then(Self::parse_region_name))
+            .filter_map(|pos| self.region(pos, false).map(|region| region.lock().chunks(pos)))
+            .flatten()
+            .collect()
+    }
+
+    pub fn read(&self, chunk: ChunkPos) -> io::Result<ChunkData> {
+        let Some(region) = self.region(region_of(chunk), false) else {
+            if self.directory.join(region_filename(region_of(chunk))).exists() {
+                return Err(io::Error::other("the region file could not be opened"));
+            }
+            return Ok(ChunkData::Missing);
+        };
+        let mut lock = region.lock();
+        lock.read(chunk)
+    }
+
+    pub fn write(&self, chunk: ChunkPos, data: &[u8]) -> bool {
+        let Some(region) = self.region(region_of(chunk), true) else {
+            return false;
+        };
+        let mut lock = region.lock();
+        if let Err(e) = lock.write(chunk, data) {
+            error!("Failed to write chunk {chunk:?} to its region: {e}");
+            false
+        } else {
+            true
+        }
+    }
+
+    pub fn flush(&self) {
+        for (pos, region) in self.open.lock().regions.iter() {
+            if let Err(e) = region.file.lock().flush() {
+                error!("Failed to flush region {pos:?}: {e}");
+            }
+        }
+    }
+}
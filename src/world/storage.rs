@@ -0,0 +1,110 @@
+use crate::utils::crc32;
+use crate::world::versioning;
+use crate::world::versioning::SaveKind;
+use log::{error, warn};
+use mvutils::save::Savable;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Marks files written by [`write_object`], files without it are from before checksums existed.
+pub const MAGIC: [u8; 4] = *b"FISV";
+pub const HEADER_SIZE: usize = 8;
+pub const TEMP_EXTENSION: &str = "tmp";
+pub const BACKUP_EXTENSION: &str = "bak";
+
+fn with_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+    path.with_file_name(name)
+}
+
+/// Writes the file so that it is either fully replaced or not touched at all. The data goes to a temporary
+/// file first which is then renamed over the target, the previous version is kept as `<name>.bak`.
+pub fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp = with_extension(path, TEMP_EXTENSION);
+    let mut file = File::create(&temp)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
+
+    if path.exists() {
+        fs::rename(path, with_extension(path, BACKUP_EXTENSION))?;
+    }
+    fs::rename(&temp, path)
+}
+
+/// Prepends the magic and a checksum of the payload.
+pub fn encode(payload: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(payload.len() + HEADER_SIZE);
+    data.extend_from_slice(&MAGIC);
+    data.extend_from_slice(&crc32(payload).to_le_bytes());
+    data.extend_from_slice(payload);
+    data
+}
+
+/// Returns the payload if the checksum matches. Files without a header are returned as is.
+pub fn decode(data: &[u8]) -> Result<&[u8], String> {
+    if data.len() < HEADER_SIZE || data[..4] != MAGIC {
+        return Ok(data);
+    }
+    let expected = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+    let payload = &data[HEADER_SIZE..];
+    if crc32(payload) == expected {
+        Ok(payload)
+    } else {
+        Err("checksum mismatch".to_string())
+    }
+}
+
+fn read_verified<T: Savable>(path: &Path, kind: SaveKind) -> Result<T, String> {
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    let payload = decode(&data)?;
+    versioning::load_versioned(kind, payload)
+}
+
+pub fn write_object<T: Savable>(path: &Path, kind: SaveKind, object: &T) -> bool {
+    if let Err(e) = write_atomic(path, &encode(&versioning::save_versioned(kind, object))) {
+        error!("Failed to save {path:?}: {e}");
+        false
+    } else {
+        true
+    }
+}
+
+/// Reads the file, falling back to the last good copy if it is missing or damaged.
+pub fn read_object<T: Savable>(path: &Path, kind: SaveKind) -> Option<T> {
+    let backup = with_extension(path, BACKUP_EXTENSION);
+    match read_verified(path, kind) {
+        Ok(object) => Some(object),
+        Err(e) => {
+            if !path.exists() && !backup.exists() {
+                return None;
+            }
+            warn!("Could not read {path:?} ({e}), trying the last good copy");
+            match read_verified(&backup, kind) {
+                Ok(object) => Some(object),
+                Err(e) => {
+                    error!("The last good copy of {path:?} is not readable either: {e}");
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Rewrites the file with the current version if it is outdated. Returns whether it was rewritten.
+pub fn upgrade_file(path: &Path, kind: SaveKind) -> Result<bool, String> {
+    let data = fs::read(path).map_err(|e| e.to_string())?;
+    let payload = decode(&data)?;
+    if !versioning::is_outdated(kind, payload) {
+        return Ok(false);
+    }
+    let upgraded = versioning::read_versioned(kind, payload)?;
+    write_atomic(path, &encode(&versioning::write_versioned(kind, &upgraded))).map_err(|e| e.to_string())?;
+    Ok(true)
+}
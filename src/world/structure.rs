@@ -0,0 +1,177 @@
+use crate::registry::biomes::Biomes;
+use crate::registry::multitiles::{MultiTiles, MULTI_REGISTRY};
+use crate::registry::terrain::TerrainTiles;
+use crate::registry::tiles::Tiles;
+use crate::registry::Registerable;
+use crate::world::generate::biome::tile_random;
+use crate::world::tiles::{Orientation, TileKind};
+use crate::world::TileExtent;
+use parsing::xml::{parse_rsx, Entity, XmlValue};
+
+/// Where and how often a structure generates.
+#[derive(Clone, Debug)]
+pub struct StructureRules {
+    /// Size of the grid cells in tiles, every cell contains at most one instance of the structure
+    pub spacing: i32,
+    /// Chance that a cell contains the structure
+    pub chance: f64,
+    /// Biomes the origin of the structure has to be in, any biome if empty
+    pub biomes: Vec<usize>,
+}
+
+/// A structure with all names resolved to ids. Positions are relative to the structure origin.
+#[derive(Clone)]
+pub struct StructureTemplate {
+    pub id: usize,
+    pub name: String,
+    pub size: TileExtent,
+    pub rules: StructureRules,
+    pub terrain: Vec<(i32, i32, u16)>,
+    /// `None` orientation keeps the default orientation of the tile
+    pub tiles: Vec<(i32, i32, TileKind, Option<Orientation>)>,
+    pub multitiles: Vec<(i32, i32, usize)>,
+}
+
+impl StructureTemplate {
+    /// The origin of the structure in the given grid cell, if there is one.
+    /// This only depends on the seed, so any chunk can compute the placements overlapping it.
+    pub fn placement_in_cell(&self, seed: u32, cell: (i32, i32)) -> Option<(i32, i32)> {
+        let seed = seed ^ (self.id as u32 + 1).wrapping_mul(0x2545F491);
+        if tile_random(seed, cell.0, cell.1) >= self.rules.chance {
+            return None;
+        }
+        let free_x = (self.rules.spacing - self.size.0).max(1);
+        let free_z = (self.rules.spacing - self.size.1).max(1);
+        let offset_x = (tile_random(seed ^ 1, cell.0, cell.1) * free_x as f64) as i32;
+        let offset_z = (tile_random(seed ^ 2, cell.0, cell.1) * free_z as f64) as i32;
+        Some((cell.0 * self.rules.spacing + offset_x, cell.1 * self.rules.spacing + offset_z))
+    }
+}
+
+pub struct StructureCreateInfo {
+    pub template: StructureTemplate,
+}
+
+impl StructureCreateInfo {
+    fn get_attrib(en: &Entity, name: &str) -> Option<String> {
+        if let Some(XmlValue::Str(s)) = en.get_attrib(name) {
+            Some(s.clone())
+        } else {
+            None
+        }
+    }
+
+    fn require(en: &Entity, name: &str) -> String {
+        Self::get_attrib(en, name).unwrap_or_else(|| panic!("{name} not found on <{}> in structure!", en.name()))
+    }
+
+    fn require_num<T: std::str::FromStr>(en: &Entity, name: &str) -> T {
+        let s = Self::require(en, name);
+        s.parse().unwrap_or_else(|_| panic!("{name}=\"{s}\" is not a valid number in structure!"))
+    }
+
+    fn children<'a>(en: &'a Entity, name: &str) -> Vec<&'a Entity> {
+        if let Some(XmlValue::Entities(e)) = en.inner() {
+            if let Some(found) = e.iter().find(|e| e.name() == name) {
+                if let Some(XmlValue::Entities(inner)) = found.inner() {
+                    return inner.iter().collect();
+                }
+            }
+        }
+        vec![]
+    }
+
+    fn find<'a>(en: &'a Entity, name: &str) -> Option<&'a Entity> {
+        if let Some(XmlValue::Entities(e)) = en.inner() {
+            e.iter().find(|e| e.name() == name)
+        } else {
+            None
+        }
+    }
+
+    /// Positions covered by a `<tile>` (single) or `<fill>` (rectangle) entry.
+    fn positions(en: &Entity) -> Vec<(i32, i32)> {
+        let x = Self::require_num::<i32>(en, "x");
+        let z = Self::require_num::<i32>(en, "z");
+        if en.name() == "fill" {
+            let width = Self::require_num::<i32>(en, "width");
+            let depth = Self::require_num::<i32>(en, "depth");
+            (0..width).flat_map(|dx| (0..depth).map(move |dz| (x + dx, z + dz))).collect()
+        } else {
+            vec![(x, z)]
+        }
+    }
+
+    pub fn read(xml: &str, terrain: &TerrainTiles, tiles: &Tiles, multitiles: &MultiTiles, biomes: &Biomes) -> Self {
+        let en = parse_rsx(xml.to_string()).unwrap();
+        if en.name() != "structure" {
+            panic!("Invalid XML for structure");
+        }
+        let name = Self::require(&en, "name");
+        let size = (Self::require_num(&en, "width"), Self::require_num(&en, "depth"));
+
+        let rules = Self::find(&en, "rules").expect("<rules> is required inside <structure>!");
+        let rules = StructureRules {
+            spacing: Self::require_num(rules, "spacing"),
+            chance: Self::require_num(rules, "chance"),
+            biomes: Self::get_attrib(rules, "biomes")
+                .map(|b| b.split(',')
+                    .map(|b| biomes.by_name(b.trim()).unwrap_or_else(|| panic!("Unknown biome {b} in structure {name}!")))
+                    .collect())
+                .unwrap_or_default(),
+        };
+
+        let mut template = StructureTemplate {
+            id: 0,
+            name: name.clone(),
+            size,
+            rules,
+            terrain: vec![],
+            tiles: vec![],
+            multitiles: vec![],
+        };
+
+        for entry in Self::children(&en, "terrain") {
+            let terrain_name = Self::require(entry, "terrain");
+            let id = terrain.by_name(&terrain_name).unwrap_or_else(|| panic!("Unknown terrain {terrain_name} in structure {name}!"));
+            for (x, z) in Self::positions(entry) {
+                template.terrain.push((x, z, id as u16));
+            }
+        }
+
+        for entry in Self::children(&en, "tiles") {
+            let tile_name = Self::require(entry, "tile");
+            let id = tiles.by_name(&tile_name).unwrap_or_else(|| panic!("Unknown tile {tile_name} in structure {name}!"));
+            let orientation = Self::get_attrib(entry, "orientation").map(|o| {
+                Orientation::from_name(&o).unwrap_or_else(|| panic!("Invalid orientation {o} in structure {name}!"))
+            });
+            for (x, z) in Self::positions(entry) {
+                //later entries override earlier ones, so doors can be cut into walls
+                template.tiles.retain(|(tx, tz, _, _)| (*tx, *tz) != (x, z));
+                template.tiles.push((x, z, id as TileKind, orientation));
+            }
+        }
+
+        for entry in Self::children(&en, "multitiles") {
+            let mt_name = Self::require(entry, "multitile");
+            let id = multitiles.by_name(&mt_name).unwrap_or_else(|| panic!("Unknown multitile {mt_name} in structure {name}!"));
+            if MULTI_REGISTRY.reference_object(id).is_none() {
+                panic!("Multitile {mt_name} is not registered!");
+            }
+            template.multitiles.push((Self::require_num(entry, "x"), Self::require_num(entry, "z"), id));
+        }
+
+        Self { template }
+    }
+}
+
+impl Registerable for StructureTemplate {
+    type CreateInfo = StructureCreateInfo;
+
+    fn with_id(id: usize, info: Self::CreateInfo) -> Self {
+        Self {
+            id,
+            ..info.template
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use bytebuffer::ByteBuffer;
+use mvutils::Savable;
+use mvutils::save::Savable;
+use crate::world::tiles::{Orientation, TileInstance};
+
+/// Keeps the chunk it is placed in loaded. It can only be placed while the world has forced chunks left, see
+/// [`World::can_force`](crate::world::World::can_force).
+#[derive(Clone, Savable)]
+pub struct ChunkLoaderTile {
+    orientation: Orientation,
+}
+
+impl ChunkLoaderTile {
+    pub fn new() -> Self {
+        Self {
+            orientation: Orientation::North,
+        }
+    }
+}
+
+impl TileInstance for ChunkLoaderTile {
+    fn save(&self, saver: &mut ByteBuffer) {
+        Savable::save(self, saver);
+    }
+
+    fn load_into(&mut self, loader: &mut ByteBuffer) -> Result<(), String> {
+        *self = Self::load(loader)?;
+        Ok(())
+    }
+
+    fn box_clone(&self) -> Box<dyn TileInstance> {
+        Box::new(self.clone())
+    }
+
+    fn has_client_state(&self) -> bool {
+        false
+    }
+
+    fn save_client_state(&self, _: &mut ByteBuffer) {}
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn forces_chunk(&self) -> bool {
+        true
+    }
+}
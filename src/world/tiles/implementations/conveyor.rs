@@ -2,7 +2,11 @@ use bytebuffer::ByteBuffer;
 use mvutils::Savable;
 use mvutils::save::Savable;
 use crate::ingredients::IngredientStack;
+use crate::server::packets::common::ClientDataPacket;
+use crate::server::packets::world::TileAction;
+use crate::world::tiles::pos::TilePos;
 use crate::world::tiles::{Orientation, TileInstance};
+use crate::world::World;
 
 #[derive(Clone, Savable)]
 pub struct Conveyor {
@@ -48,4 +52,12 @@ impl TileInstance for Conveyor {
     fn set_orientation(&mut self, orientation: Orientation) {
         self.orientation = orientation;
     }
+
+    fn on_interact(&mut self, _: &ClientDataPacket, action: TileAction, _: &TilePos, _: &mut World) -> bool {
+        if action == TileAction::Rotate {
+            self.orientation = self.orientation.rotate_cw();
+            return true;
+        }
+        false
+    }
 }
\ No newline at end of file
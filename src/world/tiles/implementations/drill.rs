@@ -0,0 +1,125 @@
+use bytebuffer::ByteBuffer;
+use mvutils::Savable;
+use mvutils::save::Savable;
+use crate::ingredients::IngredientStack;
+use crate::inventory::InventoryData;
+use crate::registry::deposits::DEPOSIT_REGISTRY;
+use crate::registry::terrain::TerrainTiles;
+use crate::world::tiles::placement::PlacementRule;
+use crate::world::tiles::pos::TilePos;
+use crate::world::tiles::{Orientation, TileInstance};
+use crate::world::World;
+
+#[derive(Clone, Savable)]
+pub struct Drill {
+    orientation: Orientation,
+    inventory: InventoryData,
+    /// Ticks it takes to extract a single ingredient
+    interval: u32,
+    progress: u32,
+    working: bool,
+}
+
+impl Drill {
+    pub fn new(interval: u32, capacity: u64) -> Self {
+        Self {
+            orientation: Orientation::North,
+            inventory: InventoryData::new(capacity, 5, false),
+            interval,
+            progress: 0,
+            working: false,
+        }
+    }
+}
+
+impl TileInstance for Drill {
+    fn save(&self, saver: &mut ByteBuffer) {
+        Savable::save(self, saver);
+    }
+
+    fn load_into(&mut self, loader: &mut ByteBuffer) -> Result<(), String> {
+        *self = Self::load(loader)?;
+        Ok(())
+    }
+
+    fn box_clone(&self) -> Box<dyn TileInstance> {
+        Box::new(self.clone())
+    }
+
+    fn has_client_state(&self) -> bool {
+        true
+    }
+
+    fn save_client_state(&self, saver: &mut ByteBuffer) {
+        self.working.save(saver);
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn placement_rule(&self, _: &TerrainTiles) -> PlacementRule {
+        PlacementRule::OnDeposit
+    }
+
+    fn has_inventory(&self, index: u8) -> bool {
+        index == 0
+    }
+
+    fn inventory(&self, index: u8) -> Option<&InventoryData> {
+        (index == 0).then_some(&self.inventory)
+    }
+
+    fn inventory_mut(&mut self, index: u8) -> Option<&mut InventoryData> {
+        (index == 0).then_some(&mut self.inventory)
+    }
+
+    fn tick(&mut self, at: &TilePos, world: &mut World) -> bool {
+        let working = world.get_deposit_at(at)
+            .and_then(|deposit| DEPOSIT_REGISTRY.reference_object(deposit.kind as usize))
+            .map(|deposit_type| deposit_type.ingredient)
+            .is_some_and(|ingredient| self.inventory.can_handle(&IngredientStack::new(ingredient, 1)));
+
+        if working {
+            self.progress += 1;
+            if self.progress >= self.interval {
+                self.progress = 0;
+                if let Some(ingredient) = world.extract_deposit(at) {
+                    self.inventory.add_stack(IngredientStack::new(ingredient, 1));
+                }
+            }
+        }
+
+        let changed = working != self.working;
+        self.working = working;
+        changed
+    }
+
+    fn catch_up(&mut self, at: &TilePos, world: &mut World, ticks: u64) -> bool {
+        let before = self.progress;
+        let total = self.progress as u64 + ticks;
+        let extracted = total / self.interval as u64;
+        self.progress = (total % self.interval as u64) as u32;
+        let mut changed = false;
+        //stops once the inventory is full or the deposit is gone, just like ticking would
+        for _ in 0..extracted {
+            let fits = world.get_deposit_at(at)
+                .and_then(|deposit| DEPOSIT_REGISTRY.reference_object(deposit.kind as usize))
+                .is_some_and(|deposit_type| self.inventory.can_handle(&IngredientStack::new(deposit_type.ingredient, 1)));
+            if !fits {
+                self.progress = 0;
+                break;
+            }
+            match world.extract_deposit(at) {
+                Some(ingredient) => self.inventory.add_stack(IngredientStack::new(ingredient, 1)),
+                None => break,
+            };
+            changed = true;
+        }
+        changed || self.progress != before
+    }
+}
@@ -1,9 +1,16 @@
 use bytebuffer::ByteBuffer;
+use crate::registry::terrain::TerrainTiles;
+use crate::world::tiles::placement::PlacementRule;
 use crate::world::tiles::{Orientation, TileInstance};
 
 pub mod lamp;
 pub mod conveyor;
 pub mod static_tile;
+pub mod tank;
+pub mod pipe;
+pub mod pump;
+pub mod drill;
+pub mod chunk_loader;
 
 pub struct Air;
 impl TileInstance for Air {
@@ -16,4 +23,6 @@ impl TileInstance for Air {
 
     fn orientation(&self) -> Orientation { Orientation::North }
     fn set_orientation(&mut self, _: Orientation) {}
+
+    fn placement_rule(&self, _: &TerrainTiles) -> PlacementRule { PlacementRule::Anywhere }
 }
\ No newline at end of file
@@ -0,0 +1,114 @@
+use bytebuffer::ByteBuffer;
+use mvutils::Savable;
+use mvutils::save::Savable;
+use crate::fluid::{FluidTank, Volume};
+use crate::registry::terrain::TerrainTiles;
+use crate::server::packets::common::ClientDataPacket;
+use crate::server::packets::world::TileAction;
+use crate::world::tiles::placement::PlacementRule;
+use crate::world::tiles::pos::TilePos;
+use crate::world::tiles::{Orientation, RelativeSide, TileInstance};
+use crate::world::World;
+
+#[derive(Clone, Copy, Savable, PartialEq, Debug)]
+pub enum PipeShape {
+    Straight,
+    Corner,
+    Tee,
+    Cross,
+}
+
+impl PipeShape {
+    /// The sides this shape connects on, relative to the pipe's orientation.
+    pub fn sides(&self) -> &'static [RelativeSide] {
+        match self {
+            PipeShape::Straight => &[RelativeSide::Front, RelativeSide::Back],
+            PipeShape::Corner => &[RelativeSide::Front, RelativeSide::Right],
+            PipeShape::Tee => &[RelativeSide::Front, RelativeSide::Right, RelativeSide::Left],
+            PipeShape::Cross => &[RelativeSide::Front, RelativeSide::Right, RelativeSide::Back, RelativeSide::Left],
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            PipeShape::Straight => PipeShape::Corner,
+            PipeShape::Corner => PipeShape::Tee,
+            PipeShape::Tee => PipeShape::Cross,
+            PipeShape::Cross => PipeShape::Straight,
+        }
+    }
+}
+
+#[derive(Clone, Savable)]
+pub struct Pipe {
+    orientation: Orientation,
+    shape: PipeShape,
+    tank: FluidTank,
+}
+
+impl Pipe {
+    pub fn new(capacity: Volume) -> Self {
+        Self {
+            orientation: Orientation::North,
+            shape: PipeShape::Straight,
+            tank: FluidTank::new(capacity),
+        }
+    }
+}
+
+impl TileInstance for Pipe {
+    fn save(&self, saver: &mut ByteBuffer) {
+        Savable::save(self, saver);
+    }
+
+    fn load_into(&mut self, loader: &mut ByteBuffer) -> Result<(), String> {
+        *self = Self::load(loader)?;
+        Ok(())
+    }
+
+    fn box_clone(&self) -> Box<dyn TileInstance> {
+        Box::new(self.clone())
+    }
+
+    fn has_client_state(&self) -> bool {
+        true
+    }
+
+    fn save_client_state(&self, saver: &mut ByteBuffer) {
+        self.shape.save(saver);
+        self.tank.save_client_state(saver);
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn placement_rule(&self, _: &TerrainTiles) -> PlacementRule {
+        //pipes can bridge over water
+        PlacementRule::Anywhere
+    }
+
+    fn fluid_tank(&self) -> Option<&FluidTank> {
+        Some(&self.tank)
+    }
+
+    fn fluid_tank_mut(&mut self) -> Option<&mut FluidTank> {
+        Some(&mut self.tank)
+    }
+
+    fn connects_fluid(&self, side: Orientation) -> bool {
+        self.shape.sides().contains(&self.orientation.side_of(side))
+    }
+
+    fn on_interact(&mut self, _: &ClientDataPacket, action: TileAction, _: &TilePos, _: &mut World) -> bool {
+        match action {
+            TileAction::Rotate => self.orientation = self.orientation.rotate_cw(),
+            TileAction::Use => self.shape = self.shape.next(),
+        }
+        true
+    }
+}
@@ -0,0 +1,110 @@
+use bytebuffer::ByteBuffer;
+use mvutils::Savable;
+use mvutils::save::Savable;
+use crate::fluid::{FluidTank, Volume};
+use crate::registry::terrain::TerrainTiles;
+use crate::world::tiles::placement::PlacementRule;
+use crate::world::tiles::pos::TilePos;
+use crate::world::tiles::{Orientation, TileInstance};
+use crate::world::World;
+
+/// Ticks between the checks whether a pump is still at water
+pub const WATER_CHECK_INTERVAL: u32 = 100;
+
+#[derive(Clone, Savable)]
+pub struct Pump {
+    orientation: Orientation,
+    tank: FluidTank,
+    /// Liters extracted per tick
+    rate: Volume,
+    working: bool,
+    #[unsaved]
+    at_water: Option<bool>,
+    #[unsaved]
+    water_check_in: u32,
+}
+
+impl Pump {
+    pub fn new(capacity: Volume, rate: Volume) -> Self {
+        Self {
+            orientation: Orientation::North,
+            tank: FluidTank::new(capacity),
+            rate,
+            working: false,
+            at_water: None,
+            water_check_in: 0,
+        }
+    }
+
+    /// The terrain might have changed since the pump was placed, so this is checked again every
+    /// [`WATER_CHECK_INTERVAL`] ticks instead of only on placement. While a neighbouring chunk is not loaded the last
+    /// result is kept and the check is retried on the next tick.
+    fn at_water(&mut self, at: &TilePos, world: &World) -> bool {
+        if self.at_water.is_none() || self.water_check_in == 0 {
+            let water = world.objects().terrain.water;
+            if let Some(at_water) = PlacementRule::OnOrNextTo(vec![water]).allows(world, at) {
+                self.at_water = Some(at_water);
+                self.water_check_in = WATER_CHECK_INTERVAL;
+            }
+        }
+        self.water_check_in = self.water_check_in.saturating_sub(1);
+        self.at_water.unwrap_or_default()
+    }
+}
+
+impl TileInstance for Pump {
+    fn save(&self, saver: &mut ByteBuffer) {
+        Savable::save(self, saver);
+    }
+
+    fn load_into(&mut self, loader: &mut ByteBuffer) -> Result<(), String> {
+        *self = Self::load(loader)?;
+        Ok(())
+    }
+
+    fn box_clone(&self) -> Box<dyn TileInstance> {
+        Box::new(self.clone())
+    }
+
+    fn has_client_state(&self) -> bool {
+        true
+    }
+
+    fn save_client_state(&self, saver: &mut ByteBuffer) {
+        self.working.save(saver);
+        self.tank.save_client_state(saver);
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn placement_rule(&self, terrain: &TerrainTiles) -> PlacementRule {
+        PlacementRule::OnOrNextTo(vec![terrain.water])
+    }
+
+    fn fluid_tank(&self) -> Option<&FluidTank> {
+        Some(&self.tank)
+    }
+
+    fn fluid_tank_mut(&mut self) -> Option<&mut FluidTank> {
+        Some(&mut self.tank)
+    }
+
+    fn tick(&mut self, at: &TilePos, world: &mut World) -> bool {
+        let water = world.objects().ingredients.water;
+        let working = self.at_water(at, world) && self.tank.fill(water, self.rate) > 0.0;
+        let changed = working != self.working;
+        self.working = working;
+        changed
+    }
+
+    fn catch_up(&mut self, at: &TilePos, world: &mut World, ticks: u64) -> bool {
+        let water = world.objects().ingredients.water;
+        self.at_water(at, world) && self.tank.fill(water, self.rate * ticks as f32) > 0.0
+    }
+}
@@ -0,0 +1,59 @@
+use bytebuffer::ByteBuffer;
+use mvutils::Savable;
+use mvutils::save::Savable;
+use crate::fluid::{FluidTank, Volume};
+use crate::world::tiles::{Orientation, TileInstance};
+
+#[derive(Clone, Savable)]
+pub struct Tank {
+    orientation: Orientation,
+    tank: FluidTank,
+}
+
+impl Tank {
+    pub fn new(capacity: Volume) -> Self {
+        Self {
+            orientation: Orientation::North,
+            tank: FluidTank::new(capacity),
+        }
+    }
+}
+
+impl TileInstance for Tank {
+    fn save(&self, saver: &mut ByteBuffer) {
+        Savable::save(self, saver);
+    }
+
+    fn load_into(&mut self, loader: &mut ByteBuffer) -> Result<(), String> {
+        *self = Self::load(loader)?;
+        Ok(())
+    }
+
+    fn box_clone(&self) -> Box<dyn TileInstance> {
+        Box::new(self.clone())
+    }
+
+    fn has_client_state(&self) -> bool {
+        true
+    }
+
+    fn save_client_state(&self, saver: &mut ByteBuffer) {
+        self.tank.save_client_state(saver);
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn fluid_tank(&self) -> Option<&FluidTank> {
+        Some(&self.tank)
+    }
+
+    fn fluid_tank_mut(&mut self) -> Option<&mut FluidTank> {
+        Some(&mut self.tank)
+    }
+}
@@ -7,21 +7,28 @@ use mvutils::bytebuffer::ByteBufferExtras;
 use mvutils::Savable;
 use mvutils::save::{Loader, Savable, Saver};
 use parking_lot::RwLock;
+use crate::fluid::FluidTank;
 use crate::inventory::InventoryData;
 use crate::meta::Meta;
 use crate::registry::Registerable;
 use crate::registry::tiles::TILE_REGISTRY;
+use crate::server::packets::common::ClientDataPacket;
+use crate::server::packets::world::TileAction;
 use crate::world::chunk::ToClientObject;
 use crate::world::tiles::implementations::Air;
+use crate::registry::terrain::TerrainTiles;
+use crate::world::tiles::placement::PlacementRule;
 use crate::world::tiles::pos::TilePos;
 use crate::world::tiles::update::UpdateHandler;
+use crate::world::World;
 
 pub mod pos;
 pub mod terrain;
 pub mod update;
 pub mod implementations;
+pub mod placement;
 
-#[derive(Savable, Clone, Copy, Debug)]
+#[derive(Savable, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[repr(C)]
 pub enum Orientation {
     North,
@@ -31,6 +38,8 @@ pub enum Orientation {
 }
 
 impl Orientation {
+    pub const ALL: [Orientation; 4] = [Orientation::North, Orientation::East, Orientation::South, Orientation::West];
+
     pub fn apply(&self, uv: [(f32, f32); 4]) -> [(f32, f32); 4] {
         match self {
             Orientation::North => uv,
@@ -39,6 +48,120 @@ impl Orientation {
             Orientation::West => [uv[1], uv[2], uv[3], uv[0]],
         }
     }
+
+    pub fn rotate_cw(&self) -> Self {
+        match self {
+            Orientation::North => Orientation::East,
+            Orientation::East => Orientation::South,
+            Orientation::South => Orientation::West,
+            Orientation::West => Orientation::North,
+        }
+    }
+
+    pub fn rotate_ccw(&self) -> Self {
+        match self {
+            Orientation::North => Orientation::West,
+            Orientation::West => Orientation::South,
+            Orientation::South => Orientation::East,
+            Orientation::East => Orientation::North,
+        }
+    }
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Orientation::North => Orientation::South,
+            Orientation::South => Orientation::North,
+            Orientation::East => Orientation::West,
+            Orientation::West => Orientation::East,
+        }
+    }
+
+    /// The tile offset of one step in this direction. North is +z (see [`TilePos::up`]), east is +x.
+    pub fn offset(&self) -> (i32, i32) {
+        match self {
+            Orientation::North => (0, 1),
+            Orientation::South => (0, -1),
+            Orientation::East => (1, 0),
+            Orientation::West => (-1, 0),
+        }
+    }
+
+    /// Resolves a side relative to a tile facing this way into an absolute direction,
+    /// e.g. the left side of a north-facing tile is west.
+    pub fn resolve(&self, side: RelativeSide) -> Self {
+        match side {
+            RelativeSide::Front => *self,
+            RelativeSide::Right => self.rotate_cw(),
+            RelativeSide::Back => self.opposite(),
+            RelativeSide::Left => self.rotate_ccw(),
+        }
+    }
+
+    /// The inverse of [`Orientation::resolve`]: which side of a tile facing this way points towards `direction`.
+    pub fn side_of(&self, direction: Orientation) -> RelativeSide {
+        let steps = (direction.cw_index() + 4 - self.cw_index()) % 4;
+        match steps {
+            0 => RelativeSide::Front,
+            1 => RelativeSide::Right,
+            2 => RelativeSide::Back,
+            3 => RelativeSide::Left,
+            _ => unreachable!()
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "north" => Some(Orientation::North),
+            "south" => Some(Orientation::South),
+            "east" => Some(Orientation::East),
+            "west" => Some(Orientation::West),
+            _ => None
+        }
+    }
+
+    /// Position in clockwise order starting at north.
+    fn cw_index(&self) -> u8 {
+        match self {
+            Orientation::North => 0,
+            Orientation::East => 1,
+            Orientation::South => 2,
+            Orientation::West => 3,
+        }
+    }
+}
+
+/// Conversion used by [`ChunkGenerator::gen_orientation`](crate::world::generate::ChunkGenerator::gen_orientation).
+/// Only the lowest two bits are used.
+impl From<u8> for Orientation {
+    fn from(value: u8) -> Self {
+        match value & 3 {
+            0 => Orientation::North,
+            1 => Orientation::South,
+            2 => Orientation::West,
+            3 => Orientation::East,
+            _ => unreachable!()
+        }
+    }
+}
+
+impl From<Orientation> for u8 {
+    fn from(value: Orientation) -> Self {
+        match value {
+            Orientation::North => 0,
+            Orientation::South => 1,
+            Orientation::West => 2,
+            Orientation::East => 3,
+        }
+    }
+}
+
+#[derive(Savable, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(C)]
+pub enum RelativeSide {
+    Front,
+    Right,
+    Back,
+    Left
 }
 
 pub type TileType = SaveArc<RwLock<Tile>>;
@@ -122,11 +245,33 @@ pub trait TileInstance {
     fn orientation(&self) -> Orientation;
     fn set_orientation(&mut self, orientation: Orientation);
 
+    fn placement_rule(&self, terrain: &TerrainTiles) -> PlacementRule { PlacementRule::Land }
+    /// Whether the chunk of this tile is forced to stay loaded while the tile exists, see [`World::force_chunk`]
+    fn forces_chunk(&self) -> bool { false }
+
     fn has_inventory(&self, index: u8) -> bool { false }
     fn inventory(&self, index: u8) -> Option<&InventoryData> { None }
     fn inventory_mut(&mut self, index: u8) -> Option<&mut InventoryData> { None }
 
+    fn fluid_tank(&self) -> Option<&FluidTank> { None }
+    fn fluid_tank_mut(&mut self) -> Option<&mut FluidTank> { None }
+    /// Whether fluid can flow in and out of this tile on the given side
+    fn connects_fluid(&self, side: Orientation) -> bool { self.fluid_tank().is_some() }
+
+    //interaction
+    /// Called when a player interacts with this tile. While this runs the instance is taken out of the world, so the
+    /// tile at `at` has an [`Air`] instance when looked up through `world`, see [`World::with_instance`].
+    /// Returns whether the tile changed and its state needs to be synced to the clients.
+    fn on_interact(&mut self, player: &ClientDataPacket, action: TileAction, at: &TilePos, world: &mut World) -> bool { false }
+
     //update
+    /// Called every tick, like [`TileInstance::on_interact`] the tile at `at` has an [`Air`] instance meanwhile.
+    /// Returns whether the client state changed.
+    fn tick(&mut self, at: &TilePos, world: &mut World) -> bool { false }
+    /// Called before the first tick after the chunk was not simulated for `ticks` ticks, to apply what would have
+    /// happened in the meantime at once. Runs the same way as [`TileInstance::tick`].
+    /// Returns whether anything changed, the chunk is saved and the clients are synced then.
+    fn catch_up(&mut self, at: &TilePos, world: &mut World, ticks: u64) -> bool { false }
     fn update_handler(&mut self) -> Option<&mut UpdateHandler> { None }
     fn end_tick(&mut self) {}
 }
@@ -0,0 +1,41 @@
+use crate::world::tiles::pos::TilePos;
+use crate::world::World;
+
+/// Declares on which terrain a tile may be placed.
+#[derive(Clone, Debug)]
+pub enum PlacementRule {
+    Anywhere,
+    /// Anything except water and the void
+    Land,
+    /// Only on one of the given terrain ids
+    Only(Vec<usize>),
+    /// On or directly next to one of the given terrain ids
+    OnOrNextTo(Vec<usize>),
+    /// On top of any resource deposit
+    OnDeposit,
+}
+
+impl PlacementRule {
+    /// Whether the rule allows a tile at `at`, `None` if that depends on a chunk that is not loaded.
+    pub fn allows(&self, world: &World, at: &TilePos) -> Option<bool> {
+        let terrain = &world.objects().terrain;
+        let id = || world.get_terrain_at(at.clone()).map(|t| t.id as usize);
+        match self {
+            PlacementRule::Anywhere => Some(true),
+            PlacementRule::Land => id().map(|id| id != terrain.water && id != terrain.void),
+            PlacementRule::Only(ids) => id().map(|id| ids.contains(&id)),
+            PlacementRule::OnOrNextTo(ids) => {
+                let mut unknown = false;
+                for pos in std::iter::once(at.clone()).chain(at.direct_neighbours()) {
+                    match world.get_terrain_at(pos) {
+                        Some(t) if ids.contains(&(t.id as usize)) => return Some(true),
+                        Some(_) => {}
+                        None => unknown = true,
+                    }
+                }
+                (!unknown).then_some(false)
+            }
+            PlacementRule::OnDeposit => world.is_loaded(at.chunk_pos).then(|| world.get_deposit_at(at).is_some()),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::world::tiles::Orientation;
 use crate::world::{ChunkPos, PixelUnit, SingleTileUnit, TileExtent, TileUnit, CHUNK_SIZE};
 use mvengine::math::vec::Vec2;
 use mvengine::ui::geometry::{geom, SimpleRect};
@@ -66,6 +67,16 @@ impl TilePos {
         Self::new(self.raw.0 + n, self.raw.1)
     }
 
+    pub fn step(&self, orientation: Orientation, n: i32) -> Self {
+        let (dx, dz) = orientation.offset();
+        Self::new(self.raw.0 + dx * n, self.raw.1 + dz * n)
+    }
+
+    /// The neighbour on the given side together with the direction it lies in.
+    pub fn direct_neighbours_oriented(&self) -> [(Orientation, Self); 4] {
+        Orientation::ALL.map(|o| (o, self.step(o, 1)))
+    }
+
     pub fn neighbours(&self) -> [Self; 8] {
         [
             self.up(1),
@@ -0,0 +1,189 @@
+use crate::world::deposit::Deposit;
+use crate::world::generate::{GeneratorPreset, IslandSettings};
+use crate::world::{ChunkPos, DEFAULT_DAY_LENGTH};
+use bytebuffer::ByteBuffer;
+use hashbrown::{HashMap, HashSet};
+use log::debug;
+use mvutils::lazy;
+use mvutils::save::Savable;
+
+/// Starts the version header of every persisted object. Data without it predates versioning and is version 0.
+pub const VERSION_MAGIC: [u8; 4] = *b"FIVR";
+pub const VERSION_HEADER_SIZE: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SaveKind {
+    Meta,
+    Chunk,
+    Player,
+}
+
+impl SaveKind {
+    /// The version everything of this kind is written with.
+    pub fn current_version(&self) -> u32 {
+        match self {
+            //1: added the generator settings
+            //2: added the game time
+            //3: added the day length and the time of day
+            //4: added the resolved generator stages
+            //5: added the chunks forced by chunk loaders
+            SaveKind::Meta => 5,
+            //1: added deposits
+            //2: added the time it was last simulated
+            SaveKind::Chunk => 2,
+            SaveKind::Player => 0,
+        }
+    }
+}
+
+/// Turns the serialized data of one version into the data of the next version.
+pub type Migration = fn(Vec<u8>) -> Result<Vec<u8>, String>;
+
+pub struct Migrations {
+    migrations: HashMap<(SaveKind, u32), Migration>,
+}
+
+lazy! {
+    pub static MIGRATIONS: Migrations = Migrations::builtin();
+}
+
+impl Migrations {
+    pub fn new() -> Self {
+        Self {
+            migrations: HashMap::new(),
+        }
+    }
+
+    fn builtin() -> Self {
+        let mut this = Self::new();
+        this.register(SaveKind::Meta, 0, |mut data| {
+            //the generator settings were appended, worlds from before were generated by what is now the legacy preset
+            let mut buffer = ByteBuffer::new();
+            GeneratorPreset::Legacy.save(&mut buffer);
+            IslandSettings::default().save(&mut buffer);
+            data.extend_from_slice(buffer.as_bytes());
+            Ok(data)
+        });
+        this.register(SaveKind::Chunk, 0, |mut data| {
+            //the deposits were appended, old chunks simply don't have any
+            let mut buffer = ByteBuffer::new();
+            HashMap::<u16, Deposit>::new().save(&mut buffer);
+            data.extend_from_slice(buffer.as_bytes());
+            Ok(data)
+        });
+        this.register(SaveKind::Meta, 1, |mut data| {
+            //the game time was appended, the time of older worlds starts now
+            let mut buffer = ByteBuffer::new();
+            0u64.save(&mut buffer);
+            data.extend_from_slice(buffer.as_bytes());
+            Ok(data)
+        });
+        this.register(SaveKind::Meta, 2, |mut data| {
+            //the day length and the time of day were appended, older worlds start at sunrise
+            let mut buffer = ByteBuffer::new();
+            DEFAULT_DAY_LENGTH.save(&mut buffer);
+            0u32.save(&mut buffer);
+            data.extend_from_slice(buffer.as_bytes());
+            Ok(data)
+        });
+        this.register(SaveKind::Meta, 3, |data| {
+            //the resolved stages were added to the generator settings, they are resolved from the preset once
+            let mut buffer = ByteBuffer::from(data.clone());
+            String::load(&mut buffer)?;
+            u32::load(&mut buffer)?;
+            u16::load(&mut buffer)?;
+            HashSet::<ChunkPos>::load(&mut buffer)?;
+            let preset = GeneratorPreset::load(&mut buffer)?;
+            let island = IslandSettings::load(&mut buffer)?;
+            let at = buffer.get_rpos();
+
+            let mut stages = ByteBuffer::new();
+            preset.stages(&island).save(&mut stages);
+            let mut upgraded = data[..at].to_vec();
+            upgraded.extend_from_slice(stages.as_bytes());
+            upgraded.extend_from_slice(&data[at..]);
+            Ok(upgraded)
+        });
+        this.register(SaveKind::Meta, 4, |mut data| {
+            //the chunks forced by chunk loaders were appended, before they were in the forced chunks and stay there
+            let mut buffer = ByteBuffer::new();
+            HashMap::<ChunkPos, u32>::new().save(&mut buffer);
+            data.extend_from_slice(buffer.as_bytes());
+            Ok(data)
+        });
+        this.register(SaveKind::Chunk, 1, |mut data| {
+            //the time it was last simulated was appended, older chunks have nothing to catch up on
+            let mut buffer = ByteBuffer::new();
+            0u64.save(&mut buffer);
+            data.extend_from_slice(buffer.as_bytes());
+            Ok(data)
+        });
+        this
+    }
+
+    /// Registers the upgrade from `from` to `from + 1`.
+    pub fn register(&mut self, kind: SaveKind, from: u32, migration: Migration) {
+        if self.migrations.insert((kind, from), migration).is_some() {
+            panic!("Migration of {kind:?} from version {from} registered twice!");
+        }
+    }
+
+    /// Upgrades the data step by step until it reaches the current version.
+    pub fn upgrade(&self, kind: SaveKind, mut version: u32, mut data: Vec<u8>) -> Result<Vec<u8>, String> {
+        let current = kind.current_version();
+        if version > current {
+            return Err(format!("{kind:?} was saved with version {version}, which is newer than this server ({current})"));
+        }
+        while version < current {
+            let migration = self.migrations.get(&(kind, version))
+                .ok_or_else(|| format!("No migration of {kind:?} from version {version}"))?;
+            data = migration(data)?;
+            version += 1;
+        }
+        Ok(data)
+    }
+}
+
+/// Prepends the version header with the current version.
+pub fn write_versioned(kind: SaveKind, payload: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(payload.len() + VERSION_HEADER_SIZE);
+    data.extend_from_slice(&VERSION_MAGIC);
+    data.extend_from_slice(&kind.current_version().to_le_bytes());
+    data.extend_from_slice(payload);
+    data
+}
+
+/// Splits off the version header, data without one is version 0.
+pub fn split_version(data: &[u8]) -> (u32, &[u8]) {
+    if data.len() >= VERSION_HEADER_SIZE && data[..4] == VERSION_MAGIC {
+        (u32::from_le_bytes([data[4], data[5], data[6], data[7]]), &data[VERSION_HEADER_SIZE..])
+    } else {
+        (0, data)
+    }
+}
+
+/// Strips the version header and upgrades the payload to the current version.
+pub fn read_versioned(kind: SaveKind, data: &[u8]) -> Result<Vec<u8>, String> {
+    let (version, payload) = split_version(data);
+    if version < kind.current_version() {
+        debug!("Upgrading {kind:?} from version {version} to {}", kind.current_version());
+    }
+    MIGRATIONS.upgrade(kind, version, payload.to_vec())
+}
+
+/// Whether the data is written with an older version than the current one.
+pub fn is_outdated(kind: SaveKind, data: &[u8]) -> bool {
+    split_version(data).0 < kind.current_version()
+}
+
+pub fn save_versioned<T: Savable>(kind: SaveKind, object: &T) -> Vec<u8> {
+    let mut buffer = ByteBuffer::new();
+    object.save(&mut buffer);
+    write_versioned(kind, buffer.as_bytes())
+}
+
+pub fn load_versioned<T: Savable>(kind: SaveKind, data: &[u8]) -> Result<T, String> {
+    let payload = read_versioned(kind, data)?;
+    let mut buffer = ByteBuffer::from(payload);
+    T::load(&mut buffer)
+}
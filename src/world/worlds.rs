@@ -0,0 +1,157 @@
+use crate::paths;
+use crate::registry::GameObjects;
+use crate::world::generate::GeneratorSettings;
+use crate::world::{World, WorldType, META_FILENAME};
+use log::info;
+use rand::RngCore;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Holds every world that is currently loaded. There is always a default world, new players join it and players
+/// of a world that is unloaded are moved there. Worlds are keyed by [`World::key`], so every name given to a lookup
+/// is normalised with [`paths::world_key`] first.
+pub struct WorldManager {
+    worlds: HashMap<String, WorldType>,
+    default: String,
+    objects: GameObjects,
+}
+
+impl WorldManager {
+    pub fn new(default: WorldType, objects: GameObjects) -> Self {
+        let name = default.lock().key().to_string();
+        let mut worlds = HashMap::new();
+        worlds.insert(name.clone(), default);
+        Self {
+            worlds,
+            default: name,
+            objects,
+        }
+    }
+
+    /// The key of the default world.
+    pub fn default_name(&self) -> &str {
+        &self.default
+    }
+
+    pub fn default_world(&self) -> WorldType {
+        self.worlds[&self.default].clone()
+    }
+
+    pub fn get(&self, name: &str) -> Option<WorldType> {
+        paths::world_key(name).ok().and_then(|key| self.worlds.get(&key).cloned())
+    }
+
+    pub fn is_loaded(&self, name: &str) -> bool {
+        paths::world_key(name).is_ok_and(|key| self.worlds.contains_key(&key))
+    }
+
+    /// All loaded worlds, the default world first.
+    pub fn loaded(&self) -> Vec<WorldType> {
+        let mut worlds = vec![self.default_world()];
+        worlds.extend(self.worlds.iter().filter(|(name, _)| **name != self.default).map(|(_, world)| world.clone()));
+        worlds
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.worlds.keys().cloned().collect()
+    }
+
+    fn insert(&mut self, world: WorldType) -> WorldType {
+        let key = world.lock().key().to_string();
+        self.worlds.insert(key, world.clone());
+        world
+    }
+
+    /// Loads an existing world, returns it right away if it is already loaded.
+    pub fn load(&mut self, name: &str) -> Result<WorldType, String> {
+        let dir = paths::world_dir(name)?;
+        if let Some(world) = self.get(name) {
+            return Ok(world);
+        }
+        let world = World::load(name, self.objects.clone()).ok_or_else(|| if dir.exists() {
+            format!("World {name} could not be loaded, see the log")
+        } else {
+            format!("World {name} does not exist")
+        })?;
+        info!("Loaded world {name}");
+        Ok(self.insert(world))
+    }
+
+    /// Creates and loads a new world, a random seed is used if none is given.
+    pub fn create(&mut self, name: &str, seed: Option<u32>, generator: GeneratorSettings) -> Result<WorldType, String> {
+        if self.is_loaded(name) || paths::world_dir(name)?.exists() {
+            return Err(format!("World {name} already exists"));
+        }
+        let seed = seed.unwrap_or_else(|| rand::rng().next_u32());
+        let world = World::new(name, seed, generator, self.objects.clone());
+        world.lock().save();
+        info!("Created world {name} with seed {seed}");
+        Ok(self.insert(world))
+    }
+
+    /// Saves and unloads the world. Players have to be moved out of it before.
+    pub fn unload(&mut self, name: &str) -> Result<(), String> {
+        let key = paths::world_key(name)?;
+        if key == self.default {
+            return Err("The default world cannot be unloaded".to_string());
+        }
+        let world = self.worlds.remove(&key).ok_or_else(|| format!("World {name} is not loaded"))?;
+        let mut lock = world.lock();
+        lock.wait_for_backup();
+        lock.save();
+        info!("Unloaded world {name}");
+        Ok(())
+    }
+
+    /// Deletes a world from disk. Loaded worlds have to be unloaded first.
+    pub fn delete(&mut self, name: &str) -> Result<(), String> {
+        let path = paths::world_dir(name)?;
+        if self.worlds.values().any(|world| world.lock().path() == path) {
+            return Err(format!("World {name} is loaded, unload it first"));
+        }
+        if !path.join(META_FILENAME).exists() {
+            return Err(format!("World {name} does not exist"));
+        }
+        fs::remove_dir_all(&path).map_err(|e| format!("Failed to delete {path:?}: {e}"))?;
+        info!("Deleted world {name}");
+        Ok(())
+    }
+
+    pub fn save_all(&self) {
+        for world in self.worlds.values() {
+            world.lock().save();
+        }
+    }
+}
+
+/// Keys of all worlds in the storage root, loaded or not. Their directories are named by [`paths::world_key`], so
+/// these compare equal to [`WorldManager::default_name`] and the keys of the loaded worlds.
+pub fn stored_worlds() -> Vec<String> {
+    let Ok(dir) = fs::read_dir(paths::worlds_dir()) else {
+        return Vec::new();
+    };
+    let mut worlds = dir.flatten()
+        .filter(|entry| entry.path().join(META_FILENAME).exists())
+        .filter_map(|entry| entry.file_name().to_str().map(ToString::to_string))
+        .collect::<Vec<_>>();
+    worlds.sort();
+    worlds
+}
+
+/// Size of everything in the directory in bytes.
+pub fn size_on_disk(path: &Path) -> u64 {
+    let Ok(dir) = fs::read_dir(path) else {
+        return 0;
+    };
+    dir.flatten()
+        .map(|entry| {
+            let path = entry.path();
+            if path.is_dir() {
+                size_on_disk(&path)
+            } else {
+                entry.metadata().map_or(0, |m| m.len())
+            }
+        })
+        .sum()
+}